@@ -1,107 +1,1368 @@
-use crate::{Graph, Gui, Message};
-use iced::keyboard::{Key, Modifiers, key::Named, on_key_press};
+use crate::export;
+use crate::mode::ALL_MODES;
+use crate::{
+    Action, AppPaths, BootConfig, CurveRange, Effect, Graph, Gui, HelperClient, HelperLauncher,
+    ImageExport, InputSpeedSource, Keymap, Message, MouseDelta, Overlays, ParamMeta, PathPreview,
+    PersistPlan, Preferences, Profile, ReplayReport, ReplaySpeed, SpeedFeed, SpeedHistogram,
+    SpeedUnit, ThemeFileWatcher, ToolbarState, Trace, WindowPrefs,
+};
+use iced::futures::channel::oneshot;
+use iced::keyboard::{self, Key, Modifiers};
 use iced::widget::text_input;
-use iced::{Element, Result, Task, Theme, application};
-use maccel_core::{ALL_COMMON_PARAMS, ALL_LINEAR_PARAMS};
-use maccel_core::{ALL_PARAMS, ContextRef, Param, persist::ParamStore};
+use iced::{Element, Event, Result, Subscription, Task, Theme, application, event, window};
+use maccel_core::{ALL_PARAMS, AccelMode, ContextRef, Param, persist::ParamStore};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 
 #[derive(Debug)]
-pub struct App<PS: ParamStore> {
+pub struct App<PS: ParamStore, IS: InputSpeedSource> {
     context: ContextRef<PS>,
+    speed: Rc<RefCell<SpeedFeed<IS>>>,
     input_buffer: [String; ALL_PARAMS.len()],
     focused: Option<Param>,
+    histogram: SpeedHistogram,
+    recording: bool,
+    trace: Option<(Instant, Trace)>,
+    replay: Option<ReplaySpeed>,
+    replay_report: Option<ReplayReport>,
+    path_preview: Option<(Rc<[MouseDelta]>, bool)>, // (deltas, from trace)
+    theme: Theme,
+    theme_file: ThemeFileWatcher,
+    overlays: Overlays,
+    unit: SpeedUnit,
+    status: Option<String>,
+    csv_range: [String; 3],
+    modifiers: Modifiers,
+    hovered: Option<Param>,
+    params_polled: Instant,
+    escalate: Option<Escalation<PS>>,
+    helper_launch: Option<JoinHandle<anyhow::Result<HelperClient>>>,
+    offer_helper: bool,
+    boot_config: BootConfig,
+    persist_plan: Option<PersistPlan>,
+    keymap: Keymap,
+    help: bool,
+    zoom: f32,
+    window: WindowPrefs,
+    saved_prefs: Preferences,
+    /// Values params had before each edit, oldest edit first.
+    undo: Vec<Vec<(Param, f64)>>,
+    /// The param whose slider is held down, so its moves undo as one edit.
+    dragging: Option<Param>,
+    /// The preferences whose save last failed, so the error is shown once.
+    unsaved_prefs: Option<Preferences>,
+    /// The profile last saved or loaded.
+    profile: Option<String>,
+    /// An image export is being written on another thread.
+    exporting: bool,
+    paths: AppPaths,
 }
 
-impl<PS: ParamStore + 'static> App<PS> {
+/// How a store hands its writes to the privileged helper.
+#[derive(Debug)]
+struct Escalation<PS> {
+    launcher: fn(&PS) -> HelperLauncher,
+    install: fn(&mut PS, HelperClient),
+    persist: fn(&mut PS, &PersistPlan) -> Option<anyhow::Result<()>>,
+}
+
+impl<PS> Clone for Escalation<PS> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<PS> Copy for Escalation<PS> {}
+
+impl<PS: ParamStore + 'static, IS: InputSpeedSource> App<PS, IS> {
     pub fn run<G: Gui>(self) -> Result {
-        application("maccel", App::update, App::view::<G>)
-            .subscription(|_| on_key_press(App::<PS>::handle_key))
+        thread::spawn(export::system_fonts);
+        let app = application("maccel", App::update, App::view::<G>)
+            .subscription(|_| {
+                Subscription::batch([
+                    window::frames().map(Message::Tick),
+                    // focused text inputs capture most keys, so take them all
+                    // and let the keymap decide what the field needs
+                    event::listen_with(|event, status, _| match event {
+                        Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. }) => {
+                            let captured = status == event::Status::Captured;
+                            Some(Message::KeyPressed(key, modifiers, captured))
+                        }
+                        Event::Keyboard(keyboard::Event::ModifiersChanged(m)) => {
+                            Some(Message::ModifiersChanged(m))
+                        }
+                        Event::Window(window::Event::Resized(size)) => {
+                            Some(Message::WindowResized(size))
+                        }
+                        Event::Window(window::Event::Moved(p)) => Some(Message::WindowMoved(p)),
+                        _ => None,
+                    }),
+                ])
+            })
             .antialiasing(true)
-            .centered()
-            .theme(|_| Theme::CatppuccinMocha)
-            .run_with(|| (self, Task::none()))
+            .window_size(self.window.size())
+            .theme(|app| app.theme.clone());
+        match self.window.position() {
+            Some(p) => app.position(window::Position::Specific(p)),
+            None => app.centered(),
+        }
+        .run_with(|| (self, Task::none()))
     }
-    fn view<G: Gui>(&self) -> Element<Message> {
-        G::screen(Graph::new(self.context.clone()), &self.input_buffer)
+    fn view<G: Gui>(&self) -> Element<'_, Message> {
+        let plot = match &self.path_preview {
+            Some((deltas, from_trace)) => {
+                let preview = PathPreview::new(self.context.clone(), deltas.clone());
+                G::graph_div(if *from_trace {
+                    preview.with_caption("recorded speeds along a synthetic direction")
+                } else {
+                    preview
+                })
+            }
+            None => G::graph_div(self.graph()),
+        };
+        G::screen(
+            plot,
+            &self.input_buffer,
+            &self.values(),
+            ToolbarState {
+                recording_histogram: self.recording,
+                recording_trace: self.trace.is_some(),
+                replay: self.replay_report,
+                path_preview: self.path_preview.is_some(),
+                path_from_trace: self.path_preview.as_ref().is_some_and(|&(_, t)| t),
+                theme: self.theme.clone(),
+                overlays: self.overlays,
+                unit: self.unit,
+                status: self.status.clone(),
+                csv_range: self.csv_range.clone(),
+                offer_helper: self.offer_helper,
+                mode: self.context.get().current_mode,
+                can_undo: !self.undo.is_empty(),
+                exporting: self.exporting,
+                persist_changes: self
+                    .persist_plan
+                    .as_ref()
+                    .map(|plan| plan.changes.iter().map(ToString::to_string).collect()),
+                help: self.help.then(|| {
+                    self.keymap
+                        .active(self.focused.is_some())
+                        .into_iter()
+                        .map(|(action, keys)| (keys.join(", "), action.label()))
+                        .collect()
+                }),
+            },
+        )
     }
 }
 
-impl<PS: ParamStore> App<PS> {
-    pub fn new(context: ContextRef<PS>) -> Self {
-        let mut input_buffer = <[String; ALL_PARAMS.len()]>::default();
-        for &param in ALL_PARAMS {
-            input_buffer[param as usize] = context
-                .get()
-                .parameter(param)
-                .map(|p| p.value.to_string())
-                .unwrap_or_default();
+impl<PS: ParamStore, IS: InputSpeedSource> App<PS, IS> {
+    /// How often the store is re-read for changes made by other tools.
+    const PARAM_POLL: Duration = Duration::from_secs(1);
+    /// Steps taken by the large keyboard nudges.
+    const LARGE_NUDGE: f64 = 10.;
+    /// Edits kept for undo.
+    const MAX_UNDO: usize = 100;
+
+    /// Starts from the preferences saved by the last session.
+    pub fn new(context: ContextRef<PS>, speed_source: IS) -> Self {
+        Self::with_paths(context, speed_source, AppPaths::default())
+    }
+    /// Like [`Self::new`], reading and writing files only at `paths`.
+    pub fn with_paths(context: ContextRef<PS>, speed_source: IS, paths: AppPaths) -> Self {
+        let (prefs, prefs_warning) = Preferences::load_or_default(&paths.preferences);
+        let (keymap, keymap_error) = Keymap::load_or_default(&paths.keymap);
+        let mut app =
+            Self::with_preferences(context, speed_source, prefs, paths).with_keymap(keymap);
+        let warnings: Vec<_> = [
+            prefs_warning.map(|e| format!("{e:#}")),
+            keymap_error.map(|e| format!("{e:#}; using the default keymap")),
+            app.status.take(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if !warnings.is_empty() {
+            app.status = Some(warnings.join("; "));
         }
-        App {
+        app
+    }
+    pub fn with_preferences(
+        context: ContextRef<PS>,
+        speed_source: IS,
+        prefs: Preferences,
+        paths: AppPaths,
+    ) -> Self {
+        let mut app = App {
             context,
-            input_buffer,
+            speed: SpeedFeed::shared(speed_source),
+            input_buffer: Default::default(),
             focused: None,
+            histogram: SpeedHistogram::load(&paths.histogram).unwrap_or_default(),
+            recording: false,
+            trace: None,
+            replay: None,
+            replay_report: None,
+            path_preview: prefs
+                .path_preview
+                .then(|| (MouseDelta::synthetic().into(), false)),
+            theme: prefs.theme().unwrap_or(Theme::CatppuccinMocha),
+            theme_file: ThemeFileWatcher::new(paths.graph_theme.clone()),
+            overlays: prefs.overlays,
+            unit: prefs.unit,
+            status: None,
+            modifiers: Modifiers::empty(),
+            hovered: None,
+            params_polled: Instant::now(),
+            escalate: None,
+            helper_launch: None,
+            offer_helper: false,
+            boot_config: BootConfig::default(),
+            persist_plan: None,
+            keymap: Keymap::default(),
+            help: false,
+            zoom: prefs.zoom,
+            window: prefs.window,
+            saved_prefs: prefs.clone(),
+            undo: vec![],
+            dragging: None,
+            unsaved_prefs: None,
+            profile: None,
+            exporting: false,
+            paths,
+            csv_range: {
+                let CurveRange { from, to, step } = CurveRange::default();
+                [from, to, step].map(|v| v.to_string())
+            },
+        };
+        for &param in ALL_PARAMS {
+            app.refresh_buffer(param);
+        }
+        if let Err(e) = app.theme_file.poll(Instant::now()) {
+            app.status = Some(format!("{e:#}"));
+        }
+        app
+    }
+    /// Lets the user retry failed writes with more privileges: `launcher`
+    /// says how to reach the helper, which is then started off the UI thread
+    /// and handed to `install`, e.g.
+    /// [`EscalatingStore::use_helper`](crate::EscalatingStore::use_helper).
+    /// Once installed, `persist` writes the modprobe options through it too.
+    pub fn with_escalation(
+        mut self,
+        launcher: fn(&PS) -> HelperLauncher,
+        install: fn(&mut PS, HelperClient),
+        persist: fn(&mut PS, &PersistPlan) -> Option<anyhow::Result<()>>,
+    ) -> Self {
+        self.escalate = Some(Escalation {
+            launcher,
+            install,
+            persist,
+        });
+        self
+    }
+    pub fn with_keymap(mut self, keymap: Keymap) -> Self {
+        self.keymap = keymap;
+        self
+    }
+    /// Where "Make persistent" writes its modprobe options.
+    pub fn with_boot_config(mut self, boot_config: BootConfig) -> Self {
+        self.boot_config = boot_config;
+        self
+    }
+    fn graph(&self) -> Graph<PS, IS> {
+        Graph::new(self.context.clone(), self.speed.clone())
+            .with_percentiles(self.histogram.percentiles())
+            .with_theme_file(*self.theme_file.current())
+            .with_overlays(self.overlays)
+            .with_unit(self.unit)
+            .with_highlight(self.hovered)
+            .with_zoom(self.zoom)
+    }
+    fn preferences(&self) -> Preferences {
+        Preferences {
+            window: self.window,
+            theme: self.theme.to_string(),
+            zoom: self.zoom,
+            overlays: self.overlays,
+            unit: self.unit,
+            path_preview: self.path_preview.is_some(),
+            ..Preferences::default()
+        }
+    }
+    /// Writes the preferences if anything changed since they were last saved,
+    /// retrying on later calls if that fails.
+    fn save_preferences(&mut self) {
+        let prefs = self.preferences();
+        if prefs == self.saved_prefs {
+            return;
+        }
+        match prefs.save(&self.paths.preferences) {
+            Ok(()) => {
+                self.saved_prefs = prefs;
+                self.unsaved_prefs = None;
+            }
+            Err(e) => {
+                if self.unsaved_prefs.as_ref() != Some(&prefs) {
+                    self.status = Some(format!("{e:#}"));
+                }
+                self.unsaved_prefs = Some(prefs);
+            }
+        }
+    }
+    /// Resets a field to the stored value, shown in the selected speed unit where relevant.
+    fn refresh_buffer(&mut self, param: Param) {
+        self.input_buffer[param as usize] = self
+            .context
+            .get()
+            .parameter(param)
+            .map_or_else(String::new, |p| self.format_param(param, p.value));
+    }
+    fn format_param(&self, param: Param, value: f64) -> String {
+        if SpeedUnit::applies_to(param) {
+            let v = self.unit.from_counts_per_ms(value);
+            let s = format!("{v:.4}");
+            s.trim_end_matches('0').trim_end_matches('.').to_string()
+        } else {
+            value.to_string()
+        }
+    }
+    /// Re-reads the store, picking up changes made by other tools.
+    ///
+    /// Fields with an unsaved edit keep it, with a warning in the status line.
+    fn reload_params(&mut self) {
+        let old = self.values();
+        let edited = ALL_PARAMS
+            .iter()
+            .map(|&p| self.input_buffer[p as usize] != self.format_param(p, old[p as usize]))
+            .collect::<Vec<_>>();
+        let mode_changed = {
+            let mut context = self.context.get_mut();
+            context.reset_current_parameters();
+            match context.parameter_store.get_current_accel_mode() {
+                Ok(mode) if mode != context.current_mode => {
+                    context.current_mode = mode;
+                    true
+                }
+                _ => false,
+            }
+        };
+        let new = self.values();
+        let mut conflicts = vec![];
+        for (i, &param) in ALL_PARAMS.iter().enumerate() {
+            let changed = old[param as usize] != new[param as usize];
+            if edited[i] {
+                if changed {
+                    conflicts.push(param.display_name());
+                }
+            } else if changed {
+                self.refresh_buffer(param);
+            }
+        }
+        if !conflicts.is_empty() {
+            self.status = Some(format!(
+                "changed outside the GUI while being edited: {}",
+                conflicts.join(", ")
+            ));
+        } else if mode_changed {
+            self.status = Some("acceleration mode changed outside the GUI".to_string());
+        }
+    }
+    /// Current value of every param, indexed by `param as usize`.
+    fn values(&self) -> [f64; ALL_PARAMS.len()] {
+        let mut values = [0.; ALL_PARAMS.len()];
+        for &param in ALL_PARAMS {
+            if let Some(p) = self.context.get().parameter(param) {
+                values[param as usize] = p.value;
+            }
+        }
+        values
+    }
+    /// Params that shape the curve in the current mode, in display order.
+    fn mode_params(&self) -> Vec<Param> {
+        let mode = self.context.get().current_mode;
+        ALL_PARAMS
+            .iter()
+            .copied()
+            .filter(|&p| ParamMeta::of(p).used_in(mode))
+            .collect()
+    }
+    /// Moves focus `by` fields through the current mode's params, wrapping around.
+    fn focus_field(&mut self, by: isize) -> Effect {
+        let params = self.mode_params();
+        let Some(i) = self
+            .focused
+            .and_then(|param| params.iter().position(|&p| p == param))
+        else {
+            return Effect::None;
+        };
+        let next = params[(i as isize + by).rem_euclid(params.len() as isize) as usize];
+        self.focused = Some(next);
+        Effect::Focus(next)
+    }
+    /// Steps `by` modes through linear, natural and synchronous, wrapping around.
+    fn switch_mode(&mut self, by: isize) {
+        let current = self.context.get().current_mode;
+        let i = ALL_MODES.iter().position(|&m| m == current).unwrap_or(0) as isize;
+        self.set_mode(ALL_MODES[(i + by).rem_euclid(ALL_MODES.len() as isize) as usize]);
+    }
+    /// Returns false, with the error in the status line, if the store refused.
+    fn set_mode(&mut self, mode: AccelMode) -> bool {
+        {
+            let mut context = self.context.get_mut();
+            if let Err(e) = context.parameter_store.set_current_accel_mode(mode) {
+                self.status = Some(format!("{e:#}"));
+                return false;
+            }
+            context.current_mode = mode;
+        }
+        if self
+            .focused
+            .is_some_and(|p| !ParamMeta::of(p).used_in(mode))
+        {
+            self.focused = None;
+        }
+        true
+    }
+    /// Saves the mode and params under a fresh name the user can rename.
+    fn save_profile(&mut self) -> anyhow::Result<()> {
+        let dir = &self.paths.profiles;
+        let name = export::unused_name(dir, "profile", &["toml"]);
+        Profile::new(self.context.get().current_mode, &self.values()).save(dir, &name)?;
+        self.status = Some(format!("saved profile {name} in {}", dir.display()));
+        self.profile = Some(name);
+        Ok(())
+    }
+    /// Loads the profile after the current one, as a single undoable edit.
+    fn next_profile(&mut self) -> anyhow::Result<()> {
+        let dir = &self.paths.profiles;
+        let names = Profile::list(dir)?;
+        let next = match self
+            .profile
+            .as_ref()
+            .and_then(|p| names.iter().position(|n| n == p))
+        {
+            Some(i) => names.get((i + 1) % names.len()),
+            None => names.first(),
+        };
+        let Some(name) = next.cloned() else {
+            self.status = Some(format!("no profiles saved in {}", dir.display()));
+            return Ok(());
+        };
+        let (mode, values) = Profile::load(dir, &name)?.resolve()?;
+        self.status = Some(format!("switched to profile {name}"));
+        self.profile = Some(name);
+        if self.set_mode(mode) {
+            self.set_params(values, false);
+        }
+        Ok(())
+    }
+    fn set_param(&mut self, param: Param, value: f64) {
+        self.set_params([(param, value)], false);
+    }
+    /// Writes `values` as one undoable edit; with `merge`, an edit of the same
+    /// params right before it is extended instead, as while dragging a slider.
+    fn set_params(&mut self, values: impl IntoIterator<Item = (Param, f64)>, merge: bool) {
+        self.dragging = None;
+        let old = self.values();
+        let mut edit = vec![];
+        for (param, value) in values {
+            self.write_param(param, value);
+            if self.values()[param as usize] != old[param as usize] {
+                edit.push((param, old[param as usize]));
+            }
+        }
+        let same_params =
+            |last: &Vec<(Param, f64)>| last.iter().map(|e| e.0).eq(edit.iter().map(|e| e.0));
+        if edit.is_empty() || merge && self.undo.last().is_some_and(same_params) {
+            return;
+        }
+        self.undo.push(edit);
+        if self.undo.len() > Self::MAX_UNDO {
+            self.undo.remove(0);
+        }
+    }
+    fn write_param(&mut self, param: Param, value: f64) {
+        let result = self.context.get_mut().update_param_value(param, value);
+        if let Err(e) = result {
+            self.offer_helper = self.escalate.is_some() && crate::helper::is_permission_error(&e);
+            self.status = Some(format!("{e:#}"));
+        }
+        self.refresh_buffer(param);
+    }
+    /// Installs the helper once its launch thread is done.
+    fn finish_helper_launch(&mut self) {
+        let (Some(launch), Some(Escalation { install, .. })) =
+            (self.helper_launch.take(), self.escalate)
+        else {
+            return;
+        };
+        let result = launch
+            .join()
+            .unwrap_or_else(|_| Err(anyhow::anyhow!("privileged helper launch panicked")));
+        self.status = Some(match result {
+            Ok(helper) => {
+                install(&mut self.context.get_mut().parameter_store, helper);
+                "writing parameters through the privileged helper".to_string()
+            }
+            Err(e) => format!("{e:#}"),
+        });
+    }
+    fn refresh_speed_buffers(&mut self) {
+        for &param in ALL_PARAMS.iter().filter(|&&p| SpeedUnit::applies_to(p)) {
+            self.refresh_buffer(param);
         }
     }
     fn update(&mut self, msg: Message) -> Task<Message> {
+        match self.apply(msg) {
+            Effect::None => Task::none(),
+            Effect::Focus(param) => text_input::focus(param.name()),
+            Effect::ExportImage(export) => {
+                let (done, result) = oneshot::channel();
+                thread::spawn(move || {
+                    let _ = done.send(export.write().map_err(|e| format!("{e:#}")));
+                });
+                Task::perform(result, |result| {
+                    Message::ImageExported(
+                        result.unwrap_or_else(|_| Err("image export panicked".to_string())),
+                    )
+                })
+            }
+        }
+    }
+    /// Applies a message to the app state, leaving anything that needs the
+    /// iced runtime to the returned [`Effect`].
+    pub fn apply(&mut self, msg: Message) -> Effect {
         match msg {
             Message::FieldInput(param, s) => {
                 self.input_buffer[param as usize] = s;
-                self.focused = Some(param);
             }
             Message::FieldUpdate(param) => {
-                if let Ok(f) = self.input_buffer[param as usize].parse::<f64>() {
-                    self.context
-                        .get_mut()
-                        .update_param_value(param, f)
-                        .expect("failed updating param");
-                }
-                self.input_buffer[param as usize] = self
-                    .context
-                    .get()
-                    .parameter(param)
-                    .map_or_else(String::new, |p| p.value.to_string());
-            }
-            Message::NextField => {
-                if let Some(param) = self.focused {
-                    let next = ALL_COMMON_PARAMS
-                        .iter()
-                        .chain(ALL_LINEAR_PARAMS)
-                        .cycle()
-                        .skip_while(|&&p| p != param)
-                        .nth(1)
-                        .unwrap();
-                    self.focused = Some(*next);
-                    return text_input::focus(next.name());
-                }
-            }
-            Message::PrevField => {
-                if let Some(param) = self.focused {
-                    let prev = ALL_COMMON_PARAMS
-                        .iter()
-                        .chain(ALL_LINEAR_PARAMS)
-                        .rev()
-                        .cycle()
-                        .skip_while(|&&p| p != param)
-                        .nth(1)
-                        .unwrap();
-                    self.focused = Some(*prev);
-                    return text_input::focus(prev.name());
-                }
-            }
-            Message::NextMode => todo!(),
-            Message::PrevMode => todo!(),
-        }
-        Task::none()
-    }
-    fn handle_key(key: Key, modi: Modifiers) -> Option<Message> {
-        match key {
-            Key::Named(Named::Tab) if modi == Modifiers::empty() => Some(Message::NextField),
-            Key::Named(Named::ArrowDown) => Some(Message::NextField),
-            Key::Named(Named::Tab) if modi == Modifiers::SHIFT => Some(Message::PrevField),
-            Key::Named(Named::ArrowUp) => Some(Message::PrevField),
-            Key::Named(Named::ArrowRight) => Some(Message::NextMode),
-            Key::Named(Named::ArrowLeft) => Some(Message::PrevMode),
-            _ => None,
+                let current = self.values()[param as usize];
+                if self.input_buffer[param as usize] == self.format_param(param, current) {
+                    // unchanged; parsing the rounded display would drift the value
+                } else if let Ok(mut f) = self.input_buffer[param as usize].parse::<f64>() {
+                    if SpeedUnit::applies_to(param) {
+                        f = self.unit.to_counts_per_ms(f);
+                    }
+                    match ParamMeta::validate(param, f) {
+                        Ok(f) => {
+                            self.status = ParamMeta::warn_outside_range(param, f);
+                            self.set_param(param, f);
+                        }
+                        Err(e) => {
+                            self.status = Some(format!("{e:#}"));
+                            self.refresh_buffer(param);
+                        }
+                    }
+                } else {
+                    self.refresh_buffer(param);
+                }
+            }
+            Message::SliderInput(param, v) => {
+                let merge = self.dragging == Some(param);
+                self.set_params([(param, v)], merge);
+                self.dragging = Some(param);
+            }
+            Message::SliderReleased => self.dragging = None,
+            Message::StepParam(param, steps) => {
+                let scale = if self.modifiers.shift() {
+                    0.1
+                } else if self.modifiers.control() {
+                    10.
+                } else {
+                    1.
+                };
+                let v =
+                    ParamMeta::of(param).nudge(self.values()[param as usize], steps as f64 * scale);
+                self.set_param(param, v);
+            }
+            Message::NudgeParam(param, steps) => {
+                let v = ParamMeta::of(param).nudge(self.values()[param as usize], steps);
+                self.set_param(param, v);
+            }
+            Message::ModifiersChanged(modifiers) => self.modifiers = modifiers,
+            Message::FieldFocus(param, true) => self.focused = Some(param),
+            Message::FieldFocus(param, false) => {
+                if self.focused == Some(param) {
+                    self.focused = None;
+                }
+            }
+            Message::KeyPressed(key, modi, captured) => {
+                if let Some(msg) = self.handle_key(key, modi, captured) {
+                    return self.apply(msg);
+                }
+            }
+            Message::ToggleHelp => self.help = !self.help,
+            Message::UseHelper => {
+                if let Some(Escalation { launcher, .. }) = self.escalate {
+                    if self.helper_launch.is_none() {
+                        let launcher = launcher(&self.context.get().parameter_store);
+                        self.helper_launch = Some(thread::spawn(move || launcher.run()));
+                        self.status = Some("waiting for the privileged helper".to_string());
+                    }
+                    self.offer_helper = false;
+                }
+            }
+            Message::MakePersistent => {
+                let mode = self.context.get().current_mode;
+                match self.boot_config.plan(mode, &self.values()) {
+                    Ok(plan) if plan.is_noop() => {
+                        self.status = Some(format!(
+                            "{} already matches",
+                            self.boot_config.path().display()
+                        ));
+                    }
+                    Ok(plan) => self.persist_plan = Some(plan),
+                    Err(e) => self.status = Some(format!("{e:#}")),
+                }
+            }
+            Message::ConfirmPersist => {
+                if let Some(plan) = self.persist_plan.take() {
+                    let via_helper = self.escalate.and_then(|e| {
+                        (e.persist)(&mut self.context.get_mut().parameter_store, &plan)
+                    });
+                    let result = via_helper.unwrap_or_else(|| self.boot_config.write(&plan));
+                    self.status = Some(match result {
+                        Ok(()) => format!("wrote {}", self.boot_config.path().display()),
+                        Err(e) => {
+                            // keep the review open so the write can be retried
+                            // once the helper is running
+                            if self.escalate.is_some() && crate::helper::is_permission_error(&e) {
+                                self.offer_helper = true;
+                                self.persist_plan = Some(plan);
+                            }
+                            format!("{e:#}")
+                        }
+                    });
+                }
+            }
+            Message::CancelPersist => self.persist_plan = None,
+            Message::ZoomGraph(lines) => {
+                let (min, max) = Graph::<PS, IS>::ZOOM_LIMITS;
+                self.zoom = (self.zoom * 1.1f32.powf(lines)).clamp(min, max);
+            }
+            Message::ResetZoom => self.zoom = 1.,
+            Message::WindowResized(size) => {
+                self.window.width = size.width;
+                self.window.height = size.height;
+            }
+            Message::WindowMoved(p) => {
+                self.window.x = Some(p.x);
+                self.window.y = Some(p.y);
+            }
+            Message::HoverParam(param) => self.hovered = param,
+            Message::ResetParam(param) => self.set_param(param, ParamMeta::of(param).default),
+            Message::ResetMode => {
+                let defaults = self.mode_params().into_iter();
+                self.set_params(defaults.map(|p| (p, ParamMeta::of(p).default)), false);
+            }
+            Message::Undo => match self.undo.pop() {
+                Some(edit) => {
+                    for (param, value) in edit {
+                        self.write_param(param, value);
+                    }
+                }
+                None => self.status = Some("nothing to undo".to_string()),
+            },
+            Message::SaveProfile => {
+                if let Err(e) = self.save_profile() {
+                    self.status = Some(format!("{e:#}"));
+                }
+            }
+            Message::NextProfile => {
+                if let Err(e) = self.next_profile() {
+                    self.status = Some(format!("{e:#}"));
+                }
+            }
+            Message::NextField => return self.focus_field(1),
+            Message::PrevField => return self.focus_field(-1),
+            Message::NextMode => self.switch_mode(1),
+            Message::PrevMode => self.switch_mode(-1),
+            Message::Tick(now) => {
+                if self.replay.as_ref().is_some_and(|r| r.is_finished(now)) {
+                    self.replay = None;
+                }
+                let speed = match &mut self.replay {
+                    Some(replay) => {
+                        let speed = replay.read_speed(now) as f32;
+                        self.speed.borrow_mut().record(speed, now);
+                        speed
+                    }
+                    None => self.speed.borrow_mut().sample(now),
+                };
+                if self.recording {
+                    self.histogram.record(speed);
+                }
+                if let Some((start, trace)) = &mut self.trace {
+                    trace.push(now.saturating_duration_since(*start), speed);
+                }
+                if let Err(e) = self.theme_file.poll(now) {
+                    self.status = Some(format!("{e:#}"));
+                }
+                if now.saturating_duration_since(self.params_polled) >= Self::PARAM_POLL {
+                    self.params_polled = now;
+                    self.reload_params();
+                    self.save_preferences();
+                }
+                if self
+                    .helper_launch
+                    .as_ref()
+                    .is_some_and(JoinHandle::is_finished)
+                {
+                    self.finish_helper_launch();
+                }
+            }
+            Message::ToggleRecording => {
+                self.recording = !self.recording;
+                if self.recording {
+                    self.histogram.clear();
+                } else if let Err(e) = self.histogram.save(&self.paths.histogram) {
+                    self.status = Some(format!("{e:#}"));
+                }
+            }
+            Message::ToggleTraceRecording => match self.trace.take() {
+                Some((_, trace)) => {
+                    if let Err(e) = trace.save(&self.paths.trace) {
+                        self.status = Some(format!("{e:#}"));
+                    }
+                }
+                None => {
+                    let values = self.values();
+                    let mode = self.context.get().current_mode;
+                    self.trace = Some((Instant::now(), Trace::new(mode, values)));
+                }
+            },
+            Message::ReplayTrace => match Trace::load(&self.paths.trace) {
+                Ok(trace) => {
+                    let context = self.context.get();
+                    let curve = || (context.current_mode, context.params_snapshot());
+                    self.replay_report = Some(trace.compare(curve(), curve()));
+                    self.replay = Some(ReplaySpeed::once(trace));
+                }
+                Err(e) => self.status = Some(format!("{e:#}")),
+            },
+            Message::TogglePathPreview => {
+                self.path_preview = match self.path_preview {
+                    Some(_) => None,
+                    None => Some((MouseDelta::synthetic().into(), false)),
+                };
+            }
+            Message::SelectTheme(theme) => self.theme = theme,
+            Message::SetOverlay(overlay, on) => self.overlays.set(overlay, on),
+            Message::SelectUnit(unit) => {
+                self.unit = unit;
+                self.refresh_speed_buffers();
+            }
+            Message::ToggleDarkMode => self.theme = Self::opposite_theme(&self.theme),
+            Message::TogglePathSource => {
+                if let Some((deltas, from_trace)) = &mut self.path_preview {
+                    if *from_trace {
+                        *deltas = MouseDelta::synthetic().into();
+                        *from_trace = false;
+                    } else {
+                        match Trace::load(&self.paths.trace) {
+                            Ok(trace) => {
+                                *deltas = MouseDelta::from_trace(&trace).into();
+                                *from_trace = true;
+                            }
+                            Err(e) => self.status = Some(format!("{e:#}")),
+                        }
+                    }
+                }
+            }
+            Message::ExportImage => {
+                if self.exporting {
+                    self.status = Some("an image export is still running".to_string());
+                } else {
+                    self.exporting = true;
+                    self.status = Some("exporting image".to_string());
+                    let export = ImageExport::new(&self.graph(), &self.theme, &self.paths.exports);
+                    return Effect::ExportImage(export);
+                }
+            }
+            Message::ImageExported(result) => {
+                self.exporting = false;
+                self.status = Some(match result {
+                    Ok([svg, png]) => format!("exported {} and {}", svg.display(), png.display()),
+                    Err(e) => e,
+                });
+            }
+            Message::CsvRangeInput(i, s) => self.csv_range[i] = s,
+            Message::ExportCsv => {
+                let [from, to, step] = &self.csv_range;
+                self.status = Some(
+                    match CurveRange::parse(from, to, step).and_then(|range| {
+                        export::export_csv(&self.graph(), range, &self.paths.exports)
+                    }) {
+                        Ok(path) => format!("exported {}", path.display()),
+                        Err(e) => format!("{e:#}"),
+                    },
+                );
+            }
+        }
+        Effect::None
+    }
+    /// The light or dark counterpart of a theme, if the family has one.
+    fn opposite_theme(theme: &Theme) -> Theme {
+        const PAIRS: &[(Theme, Theme)] = &[
+            (Theme::Light, Theme::Dark),
+            (Theme::SolarizedLight, Theme::SolarizedDark),
+            (Theme::GruvboxLight, Theme::GruvboxDark),
+            (Theme::CatppuccinLatte, Theme::CatppuccinMocha),
+            (Theme::TokyoNightLight, Theme::TokyoNight),
+            (Theme::KanagawaLotus, Theme::KanagawaWave),
+        ];
+        PAIRS
+            .iter()
+            .find_map(|(light, dark)| {
+                if theme == light {
+                    Some(dark.clone())
+                } else if theme == dark {
+                    Some(light.clone())
+                } else {
+                    None
+                }
+            })
+            .unwrap_or(if theme.extended_palette().is_dark {
+                Theme::Light
+            } else {
+                Theme::Dark
+            })
+    }
+    /// Resolves a key press through the keymap, leaving keys that edit the
+    /// focused field alone. `captured` keys were already used by some other
+    /// widget, such as the CSV range inputs.
+    pub fn handle_key(&self, key: Key, modi: Modifiers, captured: bool) -> Option<Message> {
+        let editing = captured || self.focused.is_some();
+        Some(match self.keymap.lookup(&key, modi, editing)? {
+            Action::NextField => Message::NextField,
+            Action::PrevField => Message::PrevField,
+            Action::NextMode => Message::NextMode,
+            Action::PrevMode => Message::PrevMode,
+            Action::Apply => Message::FieldUpdate(self.focused?),
+            Action::Increase => Message::NudgeParam(self.focused?, 1.),
+            Action::Decrease => Message::NudgeParam(self.focused?, -1.),
+            Action::IncreaseMore => Message::NudgeParam(self.focused?, Self::LARGE_NUDGE),
+            Action::DecreaseMore => Message::NudgeParam(self.focused?, -Self::LARGE_NUDGE),
+            Action::ZoomIn => Message::ZoomGraph(1.),
+            Action::ZoomOut => Message::ZoomGraph(-1.),
+            Action::ResetZoom => Message::ResetZoom,
+            Action::ExportImage => Message::ExportImage,
+            Action::ExportCsv => Message::ExportCsv,
+            Action::Undo => Message::Undo,
+            Action::SaveProfile => Message::SaveProfile,
+            Action::NextProfile => Message::NextProfile,
+            Action::ToggleHelp => Message::ToggleHelp,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RuntimeStore, StoreWrite, SyntheticSpeed, WriteLog};
+    use iced::keyboard::key::Named;
+    use maccel_core::{AccelMode, TuiContext};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    type TestApp = App<RuntimeStore, SyntheticSpeed>;
+
+    /// A fresh scratch directory for the app's files.
+    fn scratch_dir() -> std::path::PathBuf {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir()
+            .join(format!("maccel-gui-app-{}", std::process::id()))
+            .join(NEXT.fetch_add(1, Ordering::Relaxed).to_string());
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+    fn app(store: RuntimeStore) -> (TestApp, WriteLog) {
+        let log = store.write_log();
+        let context = ContextRef::new(TuiContext::new(store, ALL_PARAMS));
+        let paths = AppPaths::under(&scratch_dir());
+        let app = App::with_paths(context, SyntheticSpeed::new(), paths);
+        (app, log)
+    }
+    fn run(app: &mut TestApp, msgs: impl IntoIterator<Item = Message>) -> Vec<Effect> {
+        msgs.into_iter().map(|msg| app.apply(msg)).collect()
+    }
+    fn type_in(param: Param, s: &str) -> [Message; 2] {
+        [
+            Message::FieldInput(param, s.to_string()),
+            Message::FieldUpdate(param),
+        ]
+    }
+
+    #[test]
+    fn submitted_field_is_stored() {
+        let (mut app, log) = app(RuntimeStore::new());
+        run(&mut app, type_in(Param::Accel, "0.25"));
+        assert_eq!(&*log.entries(), &[StoreWrite::Param(Param::Accel, 0.25)]);
+        assert_eq!(app.input_buffer[Param::Accel as usize], "0.25");
+    }
+
+    #[test]
+    fn rejected_input_restores_the_field() {
+        let (mut app, log) = app(RuntimeStore::new().with_value(Param::Accel, 0.125));
+        run(&mut app, type_in(Param::Accel, "not a number"));
+        run(&mut app, type_in(Param::Accel, "-7"));
+        assert!(log.entries().is_empty());
+        assert_eq!(app.input_buffer[Param::Accel as usize], "0.125");
+        assert!(app.status.is_some());
+    }
+
+    #[test]
+    fn failed_preference_saves_are_reported_and_retried() {
+        let dir = scratch_dir();
+        let mut paths = AppPaths::under(&dir);
+        // a file where the directory should be makes every save fail
+        let blocker = dir.join("blocker");
+        paths.preferences = blocker.join("preferences.toml");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(&blocker, "").unwrap();
+        let context = ContextRef::new(TuiContext::new(RuntimeStore::new(), ALL_PARAMS));
+        let mut app = App::with_paths(context, SyntheticSpeed::new(), paths.clone());
+        let poll = |app: &mut TestApp| {
+            let later = app.params_polled + TestApp::PARAM_POLL;
+            app.apply(Message::Tick(later));
+        };
+
+        run(&mut app, [Message::ZoomGraph(1.)]);
+        poll(&mut app);
+        assert!(app.status.take().unwrap().contains("failed creating"));
+        poll(&mut app);
+        assert_eq!(app.status, None);
+
+        std::fs::remove_file(&blocker).unwrap();
+        poll(&mut app);
+        let saved = Preferences::load(&paths.preferences).unwrap();
+        assert_eq!(saved.zoom, app.zoom);
+    }
+
+    #[test]
+    fn image_exports_finish_off_the_ui_thread() {
+        let (mut app, _) = app(RuntimeStore::new());
+        let Effect::ExportImage(export) = app.apply(Message::ExportImage) else {
+            panic!("export was not handed off");
+        };
+        assert_eq!(app.apply(Message::ExportImage), Effect::None);
+        assert!(app.status.take().unwrap().contains("still running"));
+
+        let result = export.write().map_err(|e| format!("{e:#}"));
+        run(&mut app, [Message::ImageExported(result)]);
+        let status = app.status.take().unwrap();
+        assert!(status.starts_with("exported"), "{status}");
+        assert!(matches!(
+            app.apply(Message::ExportImage),
+            Effect::ExportImage(_)
+        ));
+    }
+
+    #[test]
+    fn reset_preferences_are_reported_at_startup() {
+        let dir = scratch_dir();
+        let paths = AppPaths::under(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(&paths.preferences, "zoom = [[[").unwrap();
+        let context = ContextRef::new(TuiContext::new(RuntimeStore::new(), ALL_PARAMS));
+        let app = App::with_paths(context, SyntheticSpeed::new(), paths);
+        assert!(app.status.unwrap().starts_with("settings were reset"));
+        assert_eq!(app.zoom, Preferences::default().zoom);
+    }
+
+    #[test]
+    fn recording_and_trace_errors_reach_the_status_line() {
+        let dir = scratch_dir();
+        let mut paths = AppPaths::under(&dir);
+        let blocker = dir.join("blocker");
+        paths.histogram = blocker.join("histogram.txt");
+        paths.trace = blocker.join("trace.txt");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(&blocker, "").unwrap();
+        let context = ContextRef::new(TuiContext::new(RuntimeStore::new(), ALL_PARAMS));
+        let mut app = App::with_paths(context, SyntheticSpeed::new(), paths);
+        let mut status_after = |msgs: &[Message]| {
+            app.status = None;
+            run(&mut app, msgs.iter().cloned());
+            app.status.take().unwrap_or_default()
+        };
+
+        let recording = [Message::ToggleRecording, Message::ToggleRecording];
+        assert!(status_after(&recording).contains("failed"));
+        let tracing = [Message::ToggleTraceRecording, Message::ToggleTraceRecording];
+        assert!(status_after(&tracing).contains("failed"));
+        assert!(status_after(&[Message::ReplayTrace]).contains("failed reading"));
+        let path_source = [Message::TogglePathPreview, Message::TogglePathSource];
+        assert!(status_after(&path_source).contains("failed reading"));
+    }
+
+    #[test]
+    fn undo_restores_whole_edits() {
+        let (mut app, log) = app(RuntimeStore::new().with_value(Param::SensMult, 2.));
+        let sens = |app: &TestApp| app.values()[Param::SensMult as usize];
+        run(&mut app, type_in(Param::Accel, "0.25"));
+        run(
+            &mut app,
+            [0.3, 0.4, 0.5].map(|v| Message::SliderInput(Param::Accel, v)),
+        );
+        run(&mut app, [Message::SliderReleased]);
+        run(&mut app, [Message::ResetMode]);
+        assert_eq!(sens(&app), 1.);
+
+        run(&mut app, [Message::Undo]);
+        assert_eq!(sens(&app), 2.);
+        assert_eq!(app.values()[Param::Accel as usize], 0.5);
+        assert_eq!(app.input_buffer[Param::SensMult as usize], "2");
+        run(&mut app, [Message::Undo]);
+        assert_eq!(app.values()[Param::Accel as usize], 0.25);
+        run(&mut app, [Message::Undo]);
+        assert_eq!(app.values()[Param::Accel as usize], 0.);
+        assert!(app.undo.is_empty());
+        let writes = log.entries().len();
+        run(&mut app, [Message::Undo]);
+        assert_eq!(log.entries().len(), writes);
+        assert_eq!(app.status.as_deref(), Some("nothing to undo"));
+    }
+
+    #[test]
+    fn profiles_are_saved_and_switched_between() {
+        let (mut app, _) = app(RuntimeStore::new());
+        run(&mut app, [Message::NextProfile]);
+        assert!(app.status.take().unwrap().starts_with("no profiles saved"));
+
+        run(&mut app, type_in(Param::Accel, "0.25"));
+        run(&mut app, [Message::SaveProfile]);
+        run(&mut app, [Message::NextMode]);
+        run(&mut app, type_in(Param::Accel, "0.5"));
+        run(&mut app, [Message::SaveProfile]);
+        assert!(app.status.take().unwrap().starts_with("saved profile"));
+        assert_eq!(Profile::list(&app.paths.profiles).unwrap().len(), 2);
+
+        run(&mut app, [Message::NextProfile]);
+        assert_eq!(app.context.get().current_mode, AccelMode::Linear);
+        assert_eq!(app.values()[Param::Accel as usize], 0.25);
+        assert!(
+            app.status
+                .take()
+                .unwrap()
+                .starts_with("switched to profile")
+        );
+        run(&mut app, [Message::Undo]);
+        assert_eq!(app.values()[Param::Accel as usize], 0.5);
+        run(&mut app, [Message::NextProfile, Message::NextProfile]);
+        assert_eq!(app.values()[Param::Accel as usize], 0.25);
+    }
+
+    #[test]
+    fn broken_keymaps_are_reported() {
+        let dir = scratch_dir();
+        let paths = AppPaths::under(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(&paths.keymap, "fly = [\"x\"]").unwrap();
+        let context = ContextRef::new(TuiContext::new(RuntimeStore::new(), ALL_PARAMS));
+        let app = App::with_paths(context, SyntheticSpeed::new(), paths);
+        assert!(app.status.unwrap().ends_with("using the default keymap"));
+        assert_eq!(app.keymap, Keymap::default());
+    }
+
+    #[test]
+    fn undo_key_is_left_to_the_field_while_editing() {
+        let (mut app, _) = app(RuntimeStore::new());
+        let undo =
+            |captured| Message::KeyPressed(Key::Character("z".into()), Modifiers::CTRL, captured);
+        run(&mut app, type_in(Param::Accel, "0.25"));
+        run(
+            &mut app,
+            [Message::FieldFocus(Param::Accel, true), undo(true)],
+        );
+        assert_eq!(app.values()[Param::Accel as usize], 0.25);
+        assert!(
+            !app.keymap
+                .active(true)
+                .iter()
+                .any(|&(action, _)| action == Action::Undo)
+        );
+        run(
+            &mut app,
+            [Message::FieldFocus(Param::Accel, false), undo(false)],
+        );
+        assert_eq!(app.values()[Param::Accel as usize], 0.);
+    }
+
+    #[test]
+    fn values_past_the_slider_are_stored_with_a_warning() {
+        let (mut app, log) = app(RuntimeStore::new());
+        run(&mut app, type_in(Param::Accel, "7"));
+        assert_eq!(&*log.entries(), &[StoreWrite::Param(Param::Accel, 7.)]);
+        assert!(app.status.as_ref().unwrap().contains("usually between"));
+        run(&mut app, type_in(Param::Accel, "0.5"));
+        assert_eq!(app.status, None);
+        run(&mut app, type_in(Param::SensMult, "0"));
+        assert!(app.status.as_ref().unwrap().contains("greater than 0"));
+    }
+
+    #[test]
+    fn failed_write_is_reported() {
+        let (mut app, log) = app(RuntimeStore::new().fail_on_set(Param::SensMult));
+        run(&mut app, type_in(Param::SensMult, "2"));
+        assert!(log.entries().is_empty());
+        assert_eq!(app.input_buffer[Param::SensMult as usize], "1");
+        assert!(
+            app.status
+                .as_deref()
+                .is_some_and(|s| s.contains("injected"))
+        );
+    }
+
+    #[test]
+    fn speeds_are_entered_in_the_selected_unit() {
+        let (mut app, log) = app(RuntimeStore::new()
+            .with_value(Param::InputDpi, 500.)
+            .with_value(Param::OffsetLinear, 2.));
+        run(&mut app, [Message::SelectUnit(SpeedUnit::CmPerSec)]);
+        assert_eq!(app.input_buffer[Param::OffsetLinear as usize], "5.08");
+        run(&mut app, [Message::SelectUnit(SpeedUnit::InchesPerSec)]);
+        assert_eq!(app.input_buffer[Param::OffsetLinear as usize], "2");
+        run(&mut app, type_in(Param::OffsetLinear, "6"));
+        assert_eq!(
+            &*log.entries(),
+            &[StoreWrite::Param(Param::OffsetLinear, 6.)]
+        );
+    }
+
+    #[test]
+    fn applying_an_untouched_rounded_field_writes_nothing() {
+        let (mut app, log) = app(RuntimeStore::new().with_value(Param::OffsetLinear, 1.23456789));
+        let stored = app.values()[Param::OffsetLinear as usize];
+        run(&mut app, [Message::SelectUnit(SpeedUnit::CmPerSec)]);
+        run(&mut app, [Message::FieldUpdate(Param::OffsetLinear)]);
+        assert!(log.entries().is_empty());
+        assert!(app.undo.is_empty());
+        assert_eq!(app.values()[Param::OffsetLinear as usize], stored);
+    }
+
+    /// Changes the store behind the app's back, then lets it poll.
+    fn change_outside(app: &mut TestApp, change: impl FnOnce(&mut RuntimeStore)) {
+        change(&mut app.context.get_mut().parameter_store);
+        let later = app.params_polled + TestApp::PARAM_POLL;
+        app.apply(Message::Tick(later));
+    }
+
+    #[test]
+    fn outside_changes_refresh_unedited_fields() {
+        let (mut app, _) = app(RuntimeStore::new());
+        change_outside(&mut app, |store| store.set(Param::Accel, 0.5).unwrap());
+        assert_eq!(app.values()[Param::Accel as usize], 0.5);
+        assert_eq!(app.input_buffer[Param::Accel as usize], "0.5");
+        assert_eq!(app.status, None);
+    }
+
+    #[test]
+    fn outside_changes_keep_edits_and_warn() {
+        let (mut app, _) = app(RuntimeStore::new());
+        run(
+            &mut app,
+            [Message::FieldInput(Param::Accel, "0.3".to_string())],
+        );
+        change_outside(&mut app, |store| store.set(Param::Accel, 0.5).unwrap());
+        assert_eq!(app.values()[Param::Accel as usize], 0.5);
+        assert_eq!(app.input_buffer[Param::Accel as usize], "0.3");
+        let status = app.status.unwrap();
+        assert!(status.contains("while being edited"), "{status}");
+        assert!(status.contains(Param::Accel.display_name()), "{status}");
+    }
+
+    #[test]
+    fn outside_mode_changes_are_reported() {
+        let (mut app, _) = app(RuntimeStore::new());
+        change_outside(&mut app, |store| {
+            store.set_current_accel_mode(AccelMode::Natural).unwrap()
+        });
+        assert_eq!(app.context.get().current_mode, AccelMode::Natural);
+        assert_eq!(
+            app.status.as_deref(),
+            Some("acceleration mode changed outside the GUI")
+        );
+    }
+
+    #[test]
+    fn focus_cycles_through_the_mode_params() {
+        let (mut app, _) = app(RuntimeStore::new());
+        assert_eq!(app.apply(Message::NextField), Effect::None);
+
+        run(&mut app, [Message::FieldFocus(Param::SensMult, true)]);
+        let params = app.mode_params();
+        assert_eq!(
+            app.apply(Message::PrevField),
+            Effect::Focus(*params.last().unwrap())
+        );
+        let effects = run(&mut app, (0..params.len()).map(|_| Message::NextField));
+        assert_eq!(
+            effects.last(),
+            Some(&Effect::Focus(*params.last().unwrap()))
+        );
+        assert_eq!(
+            app.apply(Message::NextField),
+            Effect::Focus(Param::SensMult)
+        );
+    }
+
+    #[test]
+    fn mode_switching_wraps_and_drops_foreign_focus() {
+        let (mut app, log) = app(RuntimeStore::new());
+        run(
+            &mut app,
+            [Message::FieldFocus(Param::Accel, true), Message::NextMode],
+        );
+        assert_eq!(app.context.get().current_mode, AccelMode::Natural);
+        assert_eq!(app.focused, None);
+        assert!(app.mode_params().contains(&Param::Limit));
+
+        run(&mut app, [Message::PrevMode, Message::PrevMode]);
+        assert_eq!(app.context.get().current_mode, AccelMode::Synchronous);
+        assert_eq!(
+            &*log.entries(),
+            &[
+                StoreWrite::Mode(AccelMode::Natural),
+                StoreWrite::Mode(AccelMode::Linear),
+                StoreWrite::Mode(AccelMode::Synchronous),
+            ]
+        );
+    }
+
+    #[test]
+    fn reset_mode_restores_defaults() {
+        let (mut app, log) = app(RuntimeStore::new()
+            .with_value(Param::SensMult, 2.)
+            .with_value(Param::Accel, 0.3));
+        run(&mut app, [Message::ResetMode]);
+        for param in app.mode_params() {
+            assert_eq!(app.values()[param as usize], ParamMeta::of(param).default);
         }
+        assert!(log.entries().contains(&StoreWrite::Param(Param::Accel, 0.)));
+    }
+
+    #[test]
+    fn persisting_shows_the_diff_before_writing() {
+        let root = std::env::temp_dir().join(format!("maccel-gui-app-boot-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let (app, _) = app(RuntimeStore::new().with_value(Param::SensMult, 2.));
+        let mut app = app.with_boot_config(BootConfig::new(&root));
+        let config = BootConfig::new(&root).path();
+
+        run(&mut app, [Message::MakePersistent]);
+        assert!(app.persist_plan.is_some());
+        assert!(!config.exists());
+        run(&mut app, [Message::CancelPersist, Message::MakePersistent]);
+        run(&mut app, [Message::ConfirmPersist]);
+        assert!(app.persist_plan.is_none());
+        assert!(
+            std::fs::read_to_string(&config)
+                .unwrap()
+                .contains("SENS_MULT=")
+        );
+
+        run(&mut app, [Message::MakePersistent]);
+        assert!(app.persist_plan.is_none());
+        assert!(app.status.as_ref().unwrap().ends_with("already matches"));
+    }
+
+    #[test]
+    fn keys_map_to_navigation() {
+        let (app, _) = app(RuntimeStore::new());
+        let key = |named| Key::Named(named);
+        assert_eq!(
+            app.handle_key(key(Named::Tab), Modifiers::empty(), false),
+            Some(Message::NextField)
+        );
+        assert_eq!(
+            app.handle_key(key(Named::Tab), Modifiers::SHIFT, false),
+            Some(Message::PrevField)
+        );
+        assert_eq!(
+            app.handle_key(key(Named::ArrowRight), Modifiers::empty(), false),
+            Some(Message::NextMode)
+        );
+        assert_eq!(
+            app.handle_key(key(Named::Enter), Modifiers::empty(), false),
+            None
+        );
+    }
+
+    #[test]
+    fn keys_nudge_the_focused_field() {
+        let (mut app, log) = app(RuntimeStore::new());
+        // a focused text input captures PageUp/PageDown but not Up/Down
+        let press = |key, modi, captured| Message::KeyPressed(Key::Named(key), modi, captured);
+        run(&mut app, [press(Named::ArrowUp, Modifiers::CTRL, false)]);
+        assert!(log.entries().is_empty());
+
+        run(&mut app, [Message::FieldFocus(Param::SensMult, true)]);
+        run(&mut app, [press(Named::ArrowUp, Modifiers::CTRL, false)]);
+        assert_eq!(app.values()[Param::SensMult as usize], 1.05);
+        assert_eq!(app.input_buffer[Param::SensMult as usize], "1.05");
+        run(&mut app, [press(Named::PageDown, Modifiers::empty(), true)]);
+        assert!((app.values()[Param::SensMult as usize] - 0.55).abs() < 1e-9);
+        run(&mut app, [press(Named::ArrowDown, Modifiers::CTRL, false)]);
+        assert_eq!(
+            log.entries().last(),
+            Some(&StoreWrite::Param(Param::SensMult, 0.5))
+        );
+        assert_eq!(app.context.get().current_mode, AccelMode::Linear);
+
+        log.clear();
+        run(
+            &mut app,
+            [
+                Message::FieldFocus(Param::SensMult, false),
+                press(Named::ArrowUp, Modifiers::CTRL, false),
+            ],
+        );
+        assert!(log.entries().is_empty());
+    }
+
+    #[test]
+    fn focus_follows_the_field_widgets() {
+        let (mut app, _) = app(RuntimeStore::new());
+        run(
+            &mut app,
+            [
+                Message::FieldInput(Param::Accel, "0.5".to_string()),
+                Message::FieldFocus(Param::SensMult, true),
+                // the old field's blur may arrive after the new field's focus
+                Message::FieldFocus(Param::Accel, false),
+            ],
+        );
+        assert_eq!(app.focused, Some(Param::SensMult));
+        run(&mut app, [Message::FieldFocus(Param::SensMult, false)]);
+        assert_eq!(app.focused, None);
+    }
+
+    #[test]
+    fn editing_keys_stay_in_the_focused_field() {
+        let (mut app, _) = app(RuntimeStore::new());
+        let left = || Message::KeyPressed(Key::Named(Named::ArrowLeft), Modifiers::empty(), true);
+        let help = || Message::KeyPressed(Key::Character("?".into()), Modifiers::SHIFT, true);
+        run(&mut app, [Message::FieldFocus(Param::SensMult, true)]);
+        run(&mut app, [left(), help()]);
+        assert_eq!(app.context.get().current_mode, AccelMode::Linear);
+        assert!(!app.help);
+
+        // shortcuts the field has no use for still work while it is focused
+        let reset_zoom = Message::KeyPressed(Key::Character("0".into()), Modifiers::CTRL, true);
+        run(&mut app, [Message::ZoomGraph(3.), reset_zoom]);
+        assert_eq!(app.zoom, 1.);
+
+        let [left, help] = [left(), help()].map(|msg| match msg {
+            Message::KeyPressed(key, modi, _) => Message::KeyPressed(key, modi, false),
+            msg => msg,
+        });
+        run(
+            &mut app,
+            [Message::FieldFocus(Param::SensMult, false), left, help],
+        );
+        assert_eq!(app.context.get().current_mode, AccelMode::Synchronous);
+        assert!(app.help);
     }
 }
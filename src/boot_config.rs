@@ -0,0 +1,285 @@
+use crate::dir_store::{MODE_FILE, decode, encode, mode_from_index, mode_index, write_no_follow};
+use crate::mode::mode_name;
+use anyhow::{Context, bail};
+use maccel_core::{ALL_PARAMS, AccelMode};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Where modprobe picks up the driver's load-time options, relative to the root.
+pub const MODPROBE_CONF: &str = "etc/modprobe.d/maccel.conf";
+
+const HEADER: &str = "# Written by maccel-gui; applied whenever the maccel module loads.";
+
+/// The modprobe options file that restores parameters after a reboot or
+/// module reload, under a root that is `/` outside of tests.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BootConfig {
+    root: PathBuf,
+}
+
+impl Default for BootConfig {
+    fn default() -> Self {
+        BootConfig::new("/")
+    }
+}
+
+impl BootConfig {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        BootConfig { root: root.into() }
+    }
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+    pub fn path(&self) -> PathBuf {
+        self.root.join(MODPROBE_CONF)
+    }
+
+    /// File contents setting `mode` and `values`, indexed by `param as usize`.
+    pub fn render(mode: AccelMode, values: &[f64]) -> String {
+        let mut options = format!("{MODE_FILE}={}", mode_index(mode));
+        for &param in ALL_PARAMS {
+            options += &format!(" {}={}", param.name(), encode(values[param as usize]));
+        }
+        content(&options)
+    }
+
+    /// The persisted file, or `None` if nothing was persisted yet.
+    pub fn read(&self) -> anyhow::Result<Option<String>> {
+        let path = self.path();
+        match fs::read_to_string(&path) {
+            Ok(content) => Ok(Some(content)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("failed reading {}", path.display())),
+        }
+    }
+
+    /// What persisting `mode` and `values` would change on disk.
+    pub fn plan(&self, mode: AccelMode, values: &[f64]) -> anyhow::Result<PersistPlan> {
+        let content = Self::render(mode, values);
+        let old = self.read()?.map(|c| parse_options(&c)).unwrap_or_default();
+        let new = parse_options(&content);
+        let lookup = |opts: &[(String, String)], name: &str| {
+            opts.iter().find(|(n, _)| n == name).map(|(_, v)| v.clone())
+        };
+        let mut changes: Vec<OptionChange> = new
+            .iter()
+            .map(|(name, value)| OptionChange {
+                name: name.clone(),
+                old: lookup(&old, name),
+                new: Some(value.clone()),
+            })
+            .filter(|c| c.old != c.new)
+            .collect();
+        changes.extend(
+            old.iter()
+                .filter(|(name, _)| lookup(&new, name).is_none())
+                .map(|(name, value)| OptionChange {
+                    name: name.clone(),
+                    old: Some(value.clone()),
+                    new: None,
+                }),
+        );
+        Ok(PersistPlan { content, changes })
+    }
+
+    pub fn write(&self, plan: &PersistPlan) -> anyhow::Result<()> {
+        self.write_content(&plan.content)
+    }
+
+    /// Writes the file from a [`PersistPlan::options`] line that may come from
+    /// an unprivileged client, so only the driver's own options are accepted.
+    pub fn write_options(&self, options: &str) -> anyhow::Result<()> {
+        for opt in options.split_whitespace() {
+            let (name, value) = opt
+                .split_once('=')
+                .with_context(|| format!("malformed option {opt:?}"))?;
+            let value: i64 = value
+                .parse()
+                .with_context(|| format!("{name} is not an integer"))?;
+            if name == MODE_FILE {
+                usize::try_from(value)
+                    .ok()
+                    .and_then(mode_from_index)
+                    .context("invalid mode")?;
+            } else if !ALL_PARAMS.iter().any(|p| p.name() == name) {
+                bail!("unknown option {name:?}");
+            }
+        }
+        self.write_content(&content(options))
+    }
+
+    fn write_content(&self, content: &str) -> anyhow::Result<()> {
+        let path = self.path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("failed creating {}", dir.display()))?;
+        }
+        write_no_follow(&path, content)
+    }
+}
+
+fn content(options: &str) -> String {
+    format!("{HEADER}\noptions maccel {options}\n")
+}
+
+/// `name=value` pairs from the file's `options maccel` lines, in order.
+fn parse_options(content: &str) -> Vec<(String, String)> {
+    content
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("options"))
+        .filter_map(|rest| rest.trim_start().strip_prefix("maccel "))
+        .flat_map(str::split_whitespace)
+        .filter_map(|opt| opt.split_once('='))
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+/// A rendered options file and how it differs from the one on disk.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PersistPlan {
+    pub content: String,
+    pub changes: Vec<OptionChange>,
+}
+
+/// One option being added, changed or dropped; values are raw file values.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OptionChange {
+    pub name: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+impl OptionChange {
+    fn describe(&self, raw: &Option<String>) -> String {
+        let Some(raw) = raw else {
+            return "unset".to_string();
+        };
+        let Ok(n) = raw.parse::<i64>() else {
+            return raw.clone();
+        };
+        if self.name == MODE_FILE {
+            let mode = usize::try_from(n).ok().and_then(mode_from_index);
+            mode.map_or_else(|| raw.clone(), |m| mode_name(m).to_string())
+        } else {
+            decode(n).to_string()
+        }
+    }
+}
+
+impl fmt::Display for OptionChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = ALL_PARAMS
+            .iter()
+            .find(|p| p.name() == self.name)
+            .map_or(self.name.as_str(), |p| p.display_name());
+        write!(
+            f,
+            "{name}: {} → {}",
+            self.describe(&self.old),
+            self.describe(&self.new)
+        )
+    }
+}
+
+impl PersistPlan {
+    pub fn is_noop(&self) -> bool {
+        self.changes.is_empty()
+    }
+    /// The `name=value` options this plan writes, space separated.
+    pub fn options(&self) -> String {
+        let options: Vec<_> = parse_options(&self.content)
+            .into_iter()
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect();
+        options.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ParamMeta;
+    use maccel_core::Param;
+
+    fn temp_root(name: &str) -> BootConfig {
+        let root = std::env::temp_dir()
+            .join(format!("maccel-gui-boot-{}", std::process::id()))
+            .join(name);
+        let _ = fs::remove_dir_all(&root);
+        BootConfig::new(root)
+    }
+    fn defaults() -> Vec<f64> {
+        let mut values = vec![0.; ALL_PARAMS.len()];
+        for &param in ALL_PARAMS {
+            values[param as usize] = ParamMeta::of(param).default;
+        }
+        values
+    }
+
+    #[test]
+    fn first_plan_adds_every_option() {
+        let config = temp_root("first");
+        let plan = config.plan(AccelMode::Linear, &defaults()).unwrap();
+        assert_eq!(plan.changes.len(), ALL_PARAMS.len() + 1);
+        assert!(plan.changes.iter().all(|c| c.old.is_none()));
+        assert_eq!(plan.changes[0].to_string(), "MODE: unset → linear");
+    }
+
+    #[test]
+    fn written_config_only_diffs_changed_params() {
+        let config = temp_root("diff");
+        let mut values = defaults();
+        config
+            .write(&config.plan(AccelMode::Linear, &values).unwrap())
+            .unwrap();
+        assert!(config.path().ends_with(MODPROBE_CONF));
+        assert!(config.plan(AccelMode::Linear, &values).unwrap().is_noop());
+
+        values[Param::SensMult as usize] = 1.5;
+        let plan = config.plan(AccelMode::Natural, &values).unwrap();
+        let changes: Vec<_> = plan.changes.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(changes, [MODE_FILE, Param::SensMult.name()]);
+        assert!(plan.changes[1].to_string().ends_with("→ 1.5"));
+    }
+
+    #[test]
+    fn options_missing_from_the_new_file_are_listed() {
+        let config = temp_root("dropped");
+        fs::create_dir_all(config.path().parent().unwrap()).unwrap();
+        fs::write(config.path(), "# comment\noptions maccel FOO=1\n").unwrap();
+        let plan = config.plan(AccelMode::Linear, &defaults()).unwrap();
+        let dropped = plan.changes.last().unwrap();
+        assert_eq!((dropped.name.as_str(), dropped.new.as_ref()), ("FOO", None));
+    }
+
+    #[test]
+    fn only_driver_options_are_written_from_a_line() {
+        let config = temp_root("options");
+        let plan = config.plan(AccelMode::Natural, &defaults()).unwrap();
+        config.write_options(&plan.options()).unwrap();
+        assert_eq!(config.read().unwrap().unwrap(), plan.content);
+
+        let err = |options: &str| format!("{:#}", config.write_options(options).unwrap_err());
+        assert!(err("FOO=1").contains("unknown option"));
+        assert!(err("MODE=9").contains("invalid mode"));
+        assert!(err("MODE").contains("malformed"));
+        assert!(err("MODE=1\ninstall maccel /bin/sh").contains("malformed"));
+        assert_eq!(config.read().unwrap().unwrap(), plan.content);
+    }
+
+    #[test]
+    fn symlinks_are_not_written_through() {
+        let config = temp_root("symlink");
+        let target = config.root().join("target");
+        fs::create_dir_all(config.path().parent().unwrap()).unwrap();
+        fs::write(&target, "untouched").unwrap();
+        std::os::unix::fs::symlink(&target, config.path()).unwrap();
+
+        let plan = config.plan(AccelMode::Linear, &defaults());
+        assert!(config.write(&plan.unwrap()).is_err());
+        assert!(config.write_options("MODE=1").is_err());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "untouched");
+    }
+}
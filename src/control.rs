@@ -101,3 +101,52 @@ impl From<Point> for Control {
         Control::Point(x, y)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::shorthand::*;
+    use super::*;
+    use crate::RuntimeStore;
+    use maccel_core::{ALL_PARAMS, Param, TuiContext};
+
+    fn controls(store: RuntimeStore) -> Vec<Control> {
+        Control::controls(ContextRef::new(TuiContext::new(store, ALL_PARAMS)))
+    }
+
+    #[test]
+    fn linear_controls() {
+        let store = RuntimeStore::new()
+            .with_value(Param::SensMult, 1.)
+            .with_value(Param::Accel, 0.5)
+            .with_value(Param::OffsetLinear, 2.)
+            .with_value(Param::OutputCap, 2.);
+        assert_eq!(
+            controls(store),
+            [P(2., 1.), A(2., 1., 0.5f32.atan()), P(4., 2.)]
+        );
+    }
+
+    #[test]
+    fn linear_controls_without_cap() {
+        let store = RuntimeStore::new()
+            .with_value(Param::Accel, 0.25)
+            .with_value(Param::OffsetLinear, 8.);
+        assert_eq!(controls(store), [P(8., 1.), A(8., 1., 0.25f32.atan())]);
+    }
+
+    #[test]
+    fn natural_controls() {
+        let store = RuntimeStore::new()
+            .with_mode(AccelMode::Natural)
+            .with_value(Param::SensMult, 1.5)
+            .with_value(Param::OffsetNatural, 3.)
+            .with_value(Param::Limit, 2.);
+        assert_eq!(controls(store), [P(3., 1.5), Y(3.)]);
+    }
+
+    #[test]
+    fn synchronous_has_no_controls() {
+        let store = RuntimeStore::new().with_mode(AccelMode::Synchronous);
+        assert_eq!(controls(store), []);
+    }
+}
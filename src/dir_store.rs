@@ -0,0 +1,176 @@
+use crate::mode::ALL_MODES;
+use anyhow::Context;
+use maccel_core::{AccelMode, Param, fixedptc::Fpt, persist::ParamStore};
+use std::fs;
+use std::io::Write as _;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+pub(crate) const MODE_FILE: &str = "MODE";
+
+/// Fixed-point encoding the driver parses parameter files with.
+pub(crate) fn encode(value: f64) -> i64 {
+    (value * (1u64 << 32) as f64).round() as i64
+}
+pub(crate) fn decode(raw: i64) -> f64 {
+    raw as f64 / (1u64 << 32) as f64
+}
+pub(crate) fn mode_index(mode: AccelMode) -> usize {
+    ALL_MODES.iter().position(|&m| m == mode).unwrap_or(0)
+}
+pub(crate) fn mode_from_index(i: usize) -> Option<AccelMode> {
+    ALL_MODES.get(i).copied()
+}
+
+/// Like [`fs::write`], but refuses to follow a symlink at `path`, which the
+/// privileged helper could otherwise be tricked into writing through.
+pub(crate) fn write_no_follow(path: &Path, content: &str) -> anyhow::Result<()> {
+    fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .custom_flags(libc::O_NOFOLLOW)
+        .open(path)
+        .and_then(|mut file| file.write_all(content.as_bytes()))
+        .with_context(|| format!("failed writing {}", path.display()))
+}
+
+/// Parameter files under any directory laid out like the driver's sysfs
+/// parameters, e.g. a temp dir standing in for it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DirStore {
+    root: PathBuf,
+}
+
+impl DirStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        DirStore { root: root.into() }
+    }
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Fills `root` with the given mode and values, creating it if needed.
+    pub fn populate(
+        root: impl Into<PathBuf>,
+        mode: AccelMode,
+        values: impl IntoIterator<Item = (Param, f64)>,
+    ) -> anyhow::Result<Self> {
+        let mut store = DirStore::new(root);
+        fs::create_dir_all(&store.root)
+            .with_context(|| format!("failed creating {}", store.root.display()))?;
+        store.set_current_accel_mode(mode)?;
+        for (param, value) in values {
+            store.set(param, value)?;
+        }
+        Ok(store)
+    }
+
+    fn read(&self, file: &str) -> anyhow::Result<i64> {
+        let path = self.root.join(file);
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("failed reading {}", path.display()))?;
+        content
+            .trim()
+            .parse()
+            .with_context(|| format!("{} does not hold an integer", path.display()))
+    }
+    pub(crate) fn write(&self, file: &str, value: i64) -> anyhow::Result<()> {
+        write_no_follow(&self.root.join(file), &value.to_string())
+    }
+}
+
+impl ParamStore for DirStore {
+    fn set(&mut self, param: Param, value: f64) -> anyhow::Result<()> {
+        self.write(param.name(), encode(value))
+    }
+    fn get(&self, param: Param) -> anyhow::Result<Fpt> {
+        Ok(decode(self.read(param.name())?).into())
+    }
+
+    fn set_current_accel_mode(&mut self, mode: AccelMode) -> anyhow::Result<()> {
+        self.write(MODE_FILE, mode_index(mode) as i64)
+    }
+    fn get_current_accel_mode(&self) -> anyhow::Result<AccelMode> {
+        let index = self.read(MODE_FILE)?;
+        usize::try_from(index)
+            .ok()
+            .and_then(mode_from_index)
+            .with_context(|| format!("unknown acceleration mode {index}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helper::is_permission_error;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn temp_root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir()
+            .join(format!("maccel-gui-dir-store-{}", std::process::id()))
+            .join(name);
+        let _ = fs::remove_dir_all(&root);
+        root
+    }
+    fn error(result: anyhow::Result<impl std::fmt::Debug>) -> String {
+        format!("{:#}", result.unwrap_err())
+    }
+
+    #[test]
+    fn fixed_point_round_trips() {
+        for v in [0., 1., -2.5, 0.125, 1234.0625] {
+            assert_eq!(decode(encode(v)), v);
+        }
+        assert_eq!(encode(1.), 1 << 32);
+        assert!((decode(encode(0.1)) - 0.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn populated_dir_reads_back() {
+        let root = temp_root("populate").join("nested");
+        let store = DirStore::populate(
+            &root,
+            AccelMode::Natural,
+            [(Param::SensMult, 1.5), (Param::Accel, 0.25)],
+        )
+        .unwrap();
+        assert_eq!(store.root(), root);
+        assert_eq!(store.get_current_accel_mode().unwrap(), AccelMode::Natural);
+        assert_eq!(f64::from(store.get(Param::SensMult).unwrap()), 1.5);
+        assert_eq!(f64::from(store.get(Param::Accel).unwrap()), 0.25);
+        let raw = fs::read_to_string(root.join(Param::SensMult.name())).unwrap();
+        assert_eq!(raw, encode(1.5).to_string());
+    }
+
+    #[test]
+    fn bad_files_are_reported() {
+        let root = temp_root("bad");
+        let store = DirStore::populate(&root, AccelMode::Linear, []).unwrap();
+        assert!(error(store.get(Param::Accel)).contains("failed reading"));
+
+        fs::write(root.join(Param::Accel.name()), "0.5\n").unwrap();
+        assert!(error(store.get(Param::Accel)).contains("does not hold an integer"));
+
+        fs::write(root.join(MODE_FILE), "7").unwrap();
+        assert!(error(store.get_current_accel_mode()).contains("unknown acceleration mode 7"));
+        fs::write(root.join(MODE_FILE), "-1").unwrap();
+        assert!(store.get_current_accel_mode().is_err());
+    }
+
+    #[test]
+    fn read_only_dir_is_a_permission_error() {
+        // root ignores file permissions, so there is nothing to check
+        // SAFETY: geteuid has no preconditions and cannot fail
+        if unsafe { libc::geteuid() } == 0 {
+            return;
+        }
+        let root = temp_root("read_only");
+        let mut store = DirStore::populate(&root, AccelMode::Linear, []).unwrap();
+        fs::set_permissions(&root, fs::Permissions::from_mode(0o555)).unwrap();
+        let result = store.set(Param::Accel, 0.5);
+        fs::set_permissions(&root, fs::Permissions::from_mode(0o755)).unwrap();
+        let err = result.unwrap_err();
+        assert!(is_permission_error(&err), "{err:#}");
+    }
+}
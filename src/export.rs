@@ -0,0 +1,183 @@
+use crate::{CurveRange, Graph, InputSpeedSource, SvgPainter};
+use anyhow::Context;
+use iced::{Size, Theme};
+use maccel_core::persist::ParamStore;
+use resvg::{tiny_skia, usvg};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Size exported images are drawn at, independent of the window.
+pub const EXPORT_SIZE: Size = Size::new(1200., 800.);
+
+pub fn default_dir() -> PathBuf {
+    crate::paths::data_dir().join("exports")
+}
+
+/// The graph as a standalone SVG document on the theme's background.
+pub fn to_svg<PS: ParamStore, IS: InputSpeedSource>(
+    graph: &Graph<PS, IS>,
+    theme: &Theme,
+    size: Size,
+) -> String {
+    let mut svg = SvgPainter::new(size, theme.palette().background);
+    graph.paint(&mut svg, theme, size, None);
+    svg.finish()
+}
+
+/// The system fonts, scanned once on first use.
+///
+/// Scanning takes a while, so [`App::run`](crate::App::run) starts it on a
+/// background thread before the first export needs it.
+pub fn system_fonts() -> Arc<usvg::fontdb::Database> {
+    static FONTS: OnceLock<Arc<usvg::fontdb::Database>> = OnceLock::new();
+    FONTS
+        .get_or_init(|| {
+            let mut db = usvg::fontdb::Database::new();
+            db.load_system_fonts();
+            Arc::new(db)
+        })
+        .clone()
+}
+
+/// Rasterises an SVG document with the system fonts.
+pub fn svg_to_png(svg: &str) -> anyhow::Result<Vec<u8>> {
+    let options = usvg::Options {
+        fontdb: system_fonts(),
+        ..usvg::Options::default()
+    };
+    let tree = usvg::Tree::from_str(svg, &options).context("failed parsing exported svg")?;
+    let size = tree.size().to_int_size();
+    let mut pixmap =
+        tiny_skia::Pixmap::new(size.width(), size.height()).context("export size is empty")?;
+    resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+    pixmap.encode_png().context("failed encoding png")
+}
+
+/// Curve samples as CSV; output velocity and gain are for the x axis.
+pub fn curve_csv<PS: ParamStore, IS: InputSpeedSource>(
+    graph: &Graph<PS, IS>,
+    range: CurveRange,
+) -> String {
+    let mut out = String::from("speed,x_sens,y_sens,output_velocity,gain\n");
+    let mut prev: Option<(f32, f32)> = None;
+    let samples = graph.samples(range);
+    for (i, &(speed, x_sens, y_sens)) in samples.iter().enumerate() {
+        let output = speed * x_sens;
+        // slope to the previous sample, or to the next one for the first row
+        let gain = match (prev, samples.get(i + 1)) {
+            (Some((s0, o0)), _) => (output - o0) / (speed - s0),
+            (None, Some(&(s1, x1, _))) => (s1 * x1 - output) / (s1 - speed),
+            (None, None) => x_sens,
+        };
+        let _ = writeln!(out, "{speed},{x_sens},{y_sens},{output},{gain}");
+        prev = Some((speed, output));
+    }
+    out
+}
+
+/// Writes `curve-<unix time>.csv` into `dir`.
+pub fn export_csv<PS: ParamStore, IS: InputSpeedSource>(
+    graph: &Graph<PS, IS>,
+    range: CurveRange,
+    dir: &Path,
+) -> anyhow::Result<PathBuf> {
+    let path = dir.join(format!("{}.csv", unused_name(dir, "curve", &["csv"])));
+    fs::create_dir_all(dir).with_context(|| format!("failed creating {}", dir.display()))?;
+    fs::write(&path, curve_csv(graph, range))
+        .with_context(|| format!("failed writing {}", path.display()))?;
+    Ok(path)
+}
+
+fn unix_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// `<prefix>-<unix time>`, with a counter added if a file with that name and
+/// any of `extensions` is already in `dir`.
+pub(crate) fn unused_name(dir: &Path, prefix: &str, extensions: &[&str]) -> String {
+    let base = format!("{prefix}-{}", unix_secs());
+    (1..)
+        .map(|n| match n {
+            1 => base.clone(),
+            n => format!("{base}-{n}"),
+        })
+        .find(|name| {
+            extensions
+                .iter()
+                .all(|ext| !dir.join(format!("{name}.{ext}")).exists())
+        })
+        .unwrap()
+}
+
+/// An image export with the graph already drawn, so that rasterising and
+/// writing it can happen off the UI thread.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageExport {
+    svg: String,
+    dir: PathBuf,
+    name: String,
+}
+
+impl ImageExport {
+    /// Draws `graph` for `graph-<unix time>.svg` and `.png` in `dir`.
+    pub fn new<PS: ParamStore, IS: InputSpeedSource>(
+        graph: &Graph<PS, IS>,
+        theme: &Theme,
+        dir: &Path,
+    ) -> Self {
+        ImageExport {
+            svg: to_svg(graph, theme, EXPORT_SIZE),
+            dir: dir.to_path_buf(),
+            name: unused_name(dir, "graph", &["svg", "png"]),
+        }
+    }
+    /// Writes both files, returning their paths; blocks while rasterising.
+    pub fn write(&self) -> anyhow::Result<[PathBuf; 2]> {
+        let dir = &self.dir;
+        let svg_path = dir.join(format!("{}.svg", self.name));
+        let png_path = dir.join(format!("{}.png", self.name));
+        let png = svg_to_png(&self.svg)?;
+        fs::create_dir_all(dir).with_context(|| format!("failed creating {}", dir.display()))?;
+        fs::write(&svg_path, &self.svg)
+            .with_context(|| format!("failed writing {}", svg_path.display()))?;
+        fs::write(&png_path, png)
+            .with_context(|| format!("failed writing {}", png_path.display()))?;
+        Ok([svg_path, png_path])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{IdleSpeed, RuntimeStore, SpeedFeed};
+    use maccel_core::{ALL_PARAMS, ContextRef, TuiContext};
+
+    #[test]
+    fn exports_never_overwrite_each_other() {
+        let dir = std::env::temp_dir().join(format!("maccel-gui-export-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let context = ContextRef::new(TuiContext::new(RuntimeStore::new(), ALL_PARAMS));
+        let graph = Graph::new(context, SpeedFeed::shared(IdleSpeed));
+
+        let export = || {
+            ImageExport::new(&graph, &Theme::Dark, &dir)
+                .write()
+                .unwrap()
+        };
+        let first = export();
+        let second = export();
+        assert_ne!(first, second);
+        assert!(first.iter().chain(&second).all(|p| p.exists()));
+        let png = fs::read(&second[1]).unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+
+        let range = CurveRange::default();
+        let csv = [(); 2].map(|_| export_csv(&graph, range, &dir).unwrap());
+        assert_ne!(csv[0], csv[1]);
+    }
+}
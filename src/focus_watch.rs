@@ -0,0 +1,172 @@
+use iced::advanced::layout::{self, Layout};
+use iced::advanced::widget::operation::{Focusable, Operation};
+use iced::advanced::widget::{Id, Tree, Widget, tree};
+use iced::advanced::{Clipboard, Shell, overlay, renderer};
+use iced::{Element, Event, Length, Rectangle, Size, Vector, event, mouse};
+
+/// Reports when a focusable widget, e.g. a text input, gains or loses focus.
+///
+/// Focus is checked after every event the wrapper sees, so changes made by
+/// clicks, typing and focus tasks are all picked up.
+pub struct FocusWatch<'a, Message> {
+    content: Element<'a, Message>,
+    on_change: Box<dyn Fn(bool) -> Message + 'a>,
+}
+
+impl<'a, Message> FocusWatch<'a, Message> {
+    pub fn new(
+        content: impl Into<Element<'a, Message>>,
+        on_change: impl Fn(bool) -> Message + 'a,
+    ) -> Self {
+        FocusWatch {
+            content: content.into(),
+            on_change: Box::new(on_change),
+        }
+    }
+}
+
+/// Records whether any focusable widget it visits is focused.
+struct FocusProbe(bool);
+
+impl Operation for FocusProbe {
+    fn container(
+        &mut self,
+        _id: Option<&Id>,
+        _bounds: Rectangle,
+        operate_on_children: &mut dyn FnMut(&mut dyn Operation),
+    ) {
+        operate_on_children(self);
+    }
+    fn focusable(&mut self, state: &mut dyn Focusable, _id: Option<&Id>) {
+        self.0 |= state.is_focused();
+    }
+}
+
+impl<Message> Widget<Message, iced::Theme, iced::Renderer> for FocusWatch<'_, Message> {
+    fn size(&self) -> Size<Length> {
+        self.content.as_widget().size()
+    }
+    fn size_hint(&self) -> Size<Length> {
+        self.content.as_widget().size_hint()
+    }
+    fn layout(
+        &self,
+        tree: &mut Tree,
+        renderer: &iced::Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        self.content
+            .as_widget()
+            .layout(&mut tree.children[0], renderer, limits)
+    }
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut iced::Renderer,
+        theme: &iced::Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+    ) {
+        self.content.as_widget().draw(
+            &tree.children[0],
+            renderer,
+            theme,
+            style,
+            layout,
+            cursor,
+            viewport,
+        );
+    }
+
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<bool>()
+    }
+    fn state(&self) -> tree::State {
+        tree::State::new(false)
+    }
+    fn children(&self) -> Vec<Tree> {
+        vec![Tree::new(&self.content)]
+    }
+    fn diff(&self, tree: &mut Tree) {
+        tree.diff_children(std::slice::from_ref(&self.content));
+    }
+
+    fn operate(
+        &self,
+        tree: &mut Tree,
+        layout: Layout<'_>,
+        renderer: &iced::Renderer,
+        operation: &mut dyn Operation,
+    ) {
+        self.content
+            .as_widget()
+            .operate(&mut tree.children[0], layout, renderer, operation);
+    }
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        renderer: &iced::Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        viewport: &Rectangle,
+    ) -> event::Status {
+        let status = self.content.as_widget_mut().on_event(
+            &mut tree.children[0],
+            event,
+            layout,
+            cursor,
+            renderer,
+            clipboard,
+            shell,
+            viewport,
+        );
+        let mut probe = FocusProbe(false);
+        self.content
+            .as_widget()
+            .operate(&mut tree.children[0], layout, renderer, &mut probe);
+        let focused = tree.state.downcast_mut::<bool>();
+        if *focused != probe.0 {
+            *focused = probe.0;
+            shell.publish((self.on_change)(probe.0));
+        }
+        status
+    }
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+        renderer: &iced::Renderer,
+    ) -> mouse::Interaction {
+        self.content.as_widget().mouse_interaction(
+            &tree.children[0],
+            layout,
+            cursor,
+            viewport,
+            renderer,
+        )
+    }
+    fn overlay<'b>(
+        &'b mut self,
+        tree: &'b mut Tree,
+        layout: Layout<'_>,
+        renderer: &iced::Renderer,
+        translation: Vector,
+    ) -> Option<overlay::Element<'b, Message, iced::Theme, iced::Renderer>> {
+        self.content
+            .as_widget_mut()
+            .overlay(&mut tree.children[0], layout, renderer, translation)
+    }
+}
+
+impl<'a, Message: 'a> From<FocusWatch<'a, Message>> for Element<'a, Message> {
+    fn from(watch: FocusWatch<'a, Message>) -> Self {
+        Element::new(watch)
+    }
+}
@@ -1,19 +1,158 @@
-use crate::Control;
+use crate::labels::{self, Label, format_value};
+use crate::units::nice_step;
+use crate::{Control, GraphThemeFile, InputSpeedSource, Painter, SpeedFeed, SpeedUnit};
+use anyhow::Context;
+use iced::alignment::{Horizontal, Vertical};
 use iced::mouse::Cursor;
 use iced::widget::canvas::path::Builder;
 use iced::widget::canvas::path::lyon_path::geom::euclid::{Transform2D, Vector2D};
-use iced::widget::canvas::{Frame, Geometry, Path, Program};
-use iced::{Point, Rectangle, Renderer, Size, Theme, Vector};
-use maccel_core::inputspeed::{read_input_speed, setup_input_speed_reader};
-use maccel_core::{ContextRef, persist::ParamStore, sensitivity};
+use iced::widget::canvas::{Frame, Geometry, Path, Program, Text};
+use iced::{Color, Point, Rectangle, Renderer, Size, Theme, Vector};
+use maccel_core::{AccelMode, ContextRef, Param, persist::ParamStore, sensitivity};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::ops::RangeInclusive;
+use std::rc::Rc;
 
 #[derive(Debug)]
-pub struct Graph<PS: ParamStore> {
+pub struct Graph<PS: ParamStore, IS: InputSpeedSource> {
     context: ContextRef<PS>,
+    speed: Rc<RefCell<SpeedFeed<IS>>>,
+    percentiles: Option<[f32; 3]>,
+    theme_file: GraphThemeFile,
+    overlays: Overlays,
+    unit: SpeedUnit,
+    highlight: Option<Param>,
+    zoom: f32,
 }
 
-impl<PS: ParamStore> Graph<PS> {
+/// Optional layers drawn over the curve.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Overlays {
+    pub grid: bool,
+    pub minor_grid: bool,
+    pub reference_line: bool,
+    pub speed_history: bool,
+    pub percentiles: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Overlay {
+    Grid,
+    MinorGrid,
+    ReferenceLine,
+    SpeedHistory,
+    Percentiles,
+}
+
+impl Overlay {
+    pub const ALL: [Overlay; 5] = [
+        Overlay::Grid,
+        Overlay::MinorGrid,
+        Overlay::ReferenceLine,
+        Overlay::SpeedHistory,
+        Overlay::Percentiles,
+    ];
+    pub fn label(self) -> &'static str {
+        match self {
+            Overlay::Grid => "Grid",
+            Overlay::MinorGrid => "Minor grid",
+            Overlay::ReferenceLine => "1× line",
+            Overlay::SpeedHistory => "Speed history",
+            Overlay::Percentiles => "Percentiles",
+        }
+    }
+}
+
+impl Overlays {
+    fn field(&mut self, overlay: Overlay) -> &mut bool {
+        match overlay {
+            Overlay::Grid => &mut self.grid,
+            Overlay::MinorGrid => &mut self.minor_grid,
+            Overlay::ReferenceLine => &mut self.reference_line,
+            Overlay::SpeedHistory => &mut self.speed_history,
+            Overlay::Percentiles => &mut self.percentiles,
+        }
+    }
+    pub fn get(mut self, overlay: Overlay) -> bool {
+        *self.field(overlay)
+    }
+    pub fn set(&mut self, overlay: Overlay, on: bool) {
+        *self.field(overlay) = on;
+    }
+}
+
+impl Default for Overlays {
+    fn default() -> Self {
+        Overlays {
+            grid: true,
+            minor_grid: false,
+            reference_line: true,
+            speed_history: true,
+            percentiles: true,
+        }
+    }
+}
+
+/// Input speeds to sample a curve at, in counts/ms.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CurveRange {
+    pub from: f32,
+    pub to: f32,
+    pub step: f32,
+}
+
+impl Default for CurveRange {
+    fn default() -> Self {
+        CurveRange {
+            from: 0.,
+            to: 80.,
+            step: 0.25,
+        }
+    }
+}
+
+impl CurveRange {
+    /// Most samples a range may ask for, keeping exports and plots bounded.
+    pub const MAX_SAMPLES: usize = 1_000_000;
+
+    /// Index of the last sample, capped at [`Self::MAX_SAMPLES`].
+    fn last_index(&self) -> usize {
+        // the epsilon keeps steps like 0.1 from losing the final sample
+        let n = ((self.to - self.from) as f64 / self.step as f64 + 1e-6).floor();
+        (n as usize).min(Self::MAX_SAMPLES - 1)
+    }
+
+    pub fn parse(from: &str, to: &str, step: &str) -> anyhow::Result<Self> {
+        let parse = |name, s: &str| -> anyhow::Result<f32> {
+            s.trim()
+                .parse::<f32>()
+                .ok()
+                .filter(|v| v.is_finite())
+                .with_context(|| format!("invalid {name} speed {s:?}"))
+        };
+        let range = CurveRange {
+            from: parse("start", from)?,
+            to: parse("end", to)?,
+            step: parse("step", step)?,
+        };
+        anyhow::ensure!(range.step > 0., "speed step must be positive");
+        anyhow::ensure!(range.from <= range.to, "speed range is empty");
+        anyhow::ensure!(
+            ((range.to - range.from) as f64 / range.step as f64) < Self::MAX_SAMPLES as f64,
+            "speed step is too small, asking for more than {} samples",
+            Self::MAX_SAMPLES
+        );
+        Ok(range)
+    }
+}
+
+impl<PS: ParamStore, IS: InputSpeedSource> Graph<PS, IS> {
     pub const AXIS_BOUNDS: Size = Size::new(80., 3.);
+    pub const ZOOM_LIMITS: (f32, f32) = (0.25, 8.);
+    const SPEED_TICKS: f32 = 8.;
+    const SENS_TICKS: f32 = 6.;
     pub const fn graph_area(size: Size) -> Rectangle {
         const ORIGIN_MARGIN: f32 = 40.;
         const EDGE_MARGIN: f32 = 20.;
@@ -25,31 +164,128 @@ impl<PS: ParamStore> Graph<PS> {
         }
     }
 
-    pub fn new(context: ContextRef<PS>) -> Self {
-        setup_input_speed_reader();
-        Graph { context }
+    /// Shows the speedometer of `speed` over the curve.
+    pub fn new(context: ContextRef<PS>, speed: Rc<RefCell<SpeedFeed<IS>>>) -> Self {
+        Graph {
+            context,
+            speed,
+            percentiles: None,
+            theme_file: GraphThemeFile::default(),
+            overlays: Overlays::default(),
+            unit: SpeedUnit::default(),
+            highlight: None,
+            zoom: 1.,
+        }
+    }
+    /// Shows `1 / zoom` of [`Self::AXIS_BOUNDS`] on both axes.
+    pub fn with_zoom(mut self, zoom: f32) -> Self {
+        self.zoom = zoom.clamp(Self::ZOOM_LIMITS.0, Self::ZOOM_LIMITS.1);
+        self
+    }
+    /// Marks the part of the curve and the handles `param` shapes.
+    pub fn with_highlight(mut self, highlight: Option<Param>) -> Self {
+        self.highlight = highlight;
+        self
+    }
+    pub fn with_percentiles(mut self, percentiles: Option<[f32; 3]>) -> Self {
+        self.percentiles = percentiles;
+        self
+    }
+    pub fn with_overlays(mut self, overlays: Overlays) -> Self {
+        self.overlays = overlays;
+        self
+    }
+    pub fn with_unit(mut self, unit: SpeedUnit) -> Self {
+        self.unit = unit;
+        self
+    }
+    pub fn with_theme_file(mut self, theme_file: GraphThemeFile) -> Self {
+        self.theme_file = theme_file;
+        self
+    }
+    /// Multiples of `step` within `0..=max`, excluding zero.
+    fn steps(step: f32, max: f32) -> impl Iterator<Item = f32> {
+        (1..=(max / step + 1e-3) as u32).map(move |i| i as f32 * step)
+    }
+    fn grid(axes: Size, step: Size) -> Path {
+        let mut bld = Builder::new();
+        for x in Self::steps(step.width, axes.width) {
+            bld.move_to(Point { x, y: 0. });
+            bld.line_to(Point { x, y: axes.height });
+        }
+        for y in Self::steps(step.height, axes.height) {
+            bld.move_to(Point { x: 0., y });
+            bld.line_to(Point { x: axes.width, y });
+        }
+        bld.build()
+    }
+    fn sens_at(&self, v: f32) -> (f32, f32) {
+        let context = self.context.get();
+        let (x_sens, y_sens) =
+            sensitivity(v as f64, context.current_mode, &context.params_snapshot());
+        (x_sens as f32, y_sens as f32)
+    }
+    /// Speeds and control handles shaped by `param`, given the handles from
+    /// [`Control::controls`].
+    fn influence(
+        &self,
+        param: Param,
+        controls: &[Control],
+        max_speed: f32,
+    ) -> (RangeInclusive<f32>, Vec<Point>) {
+        let handle = |i: usize| controls.get(i).map(Control::location);
+        let offset = handle(0).map_or(0., |p| p.x);
+        let (speeds, handles) = match (self.context.get().current_mode, param) {
+            (AccelMode::Linear, Param::OffsetLinear)
+            | (AccelMode::Natural, Param::OffsetNatural) => (0. ..=offset, vec![handle(0)]),
+            (AccelMode::Linear, Param::Accel) => {
+                let cap = handle(2).map_or(max_speed, |p| p.x);
+                (offset..=cap, vec![handle(1)])
+            }
+            (AccelMode::Linear, Param::OutputCap) => match handle(2) {
+                Some(p) => (p.x..=max_speed, vec![Some(p)]),
+                None => (max_speed..=max_speed, vec![]),
+            },
+            (AccelMode::Natural, Param::Limit) => (offset..=max_speed, vec![handle(1)]),
+            (AccelMode::Natural, Param::DecayRate) => (offset..=max_speed, vec![]),
+            (AccelMode::Synchronous, Param::SyncSpeed) => {
+                let sync = f64::from(self.context.get().params_snapshot().sync_speed) as f32;
+                (sync / 2. ..=sync * 2., vec![])
+            }
+            (_, Param::SensMult) => (
+                0. ..=max_speed,
+                controls.iter().map(|c| Some(c.location())).collect(),
+            ),
+            _ => (0. ..=max_speed, vec![]),
+        };
+        (speeds, handles.into_iter().flatten().collect())
+    }
+    /// `(speed, x_sens, y_sens)` for the current mode and params across `range`.
+    pub fn samples(&self, range: CurveRange) -> Vec<(f32, f32, f32)> {
+        let context = self.context.get();
+        let (mode, params) = (context.current_mode, context.params_snapshot());
+        (0..=range.last_index())
+            .map(|i| range.from + i as f32 * range.step)
+            .map(|v| {
+                let (x_sens, y_sens) = sensitivity(v as f64, mode, &params);
+                (v, x_sens as f32, y_sens as f32)
+            })
+            .collect()
     }
     fn build_plots(&self, x_bld: &mut Builder, y_bld: &mut Builder, bounds: Rectangle) {
-        const STEP_SZ: f32 = 0.25;
-
-        let mut v = bounds.x;
-        let (x_sens, y_sens) = sensitivity(
-            v as f64,
-            self.context.get().current_mode,
-            &self.context.get().params_snapshot(),
-        );
-        let (x_sens, y_sens) = (x_sens as f32, y_sens as f32);
+        let range = CurveRange {
+            from: bounds.x,
+            to: bounds.x + bounds.width,
+            ..CurveRange::default()
+        };
+        let mut samples = self.samples(range).into_iter();
+        let Some((v, x_sens, y_sens)) = samples.next() else {
+            return;
+        };
         x_bld.move_to(Point { x: v, y: x_sens });
         y_bld.move_to(Point { x: v, y: y_sens });
-        v += STEP_SZ;
 
-        while v <= bounds.x + bounds.width {
-            let (x_sens, y_sens) = sensitivity(
-                v as f64,
-                self.context.get().current_mode,
-                &self.context.get().params_snapshot(),
-            );
-            let (x_sens, y_sens) = (x_sens as f32, y_sens as f32);
+        for (v, x_sens, y_sens) in samples {
             if (bounds.y..=bounds.y + bounds.height).contains(&x_sens) {
                 x_bld.line_to(Point { x: v, y: x_sens });
             } else {
@@ -60,30 +296,89 @@ impl<PS: ParamStore> Graph<PS> {
             } else {
                 y_bld.move_to(Point { x: v, y: y_sens });
             }
-            v += STEP_SZ;
         }
     }
 }
 
-impl<M, PS: ParamStore> Program<M> for Graph<PS> {
-    type State = ();
-    fn draw(
+impl<PS: ParamStore, IS: InputSpeedSource> Graph<PS, IS> {
+    /// Draws the whole graph at `size`; `hover` is the cursor position, if any.
+    pub fn paint(
         &self,
-        _state: &Self::State,
-        renderer: &Renderer,
-        _theme: &Theme,
-        bounds: Rectangle,
-        _cursor: Cursor,
-    ) -> Vec<Geometry> {
-        let axes = Graph::<PS>::AXIS_BOUNDS;
-        let area = Graph::<PS>::graph_area(bounds.size());
-        let theme = crate::GraphTheme::default(axes, area);
+        painter: &mut impl Painter,
+        theme: &Theme,
+        size: Size,
+        hover: Option<Point>,
+    ) {
+        let axes = Self::AXIS_BOUNDS * (1. / self.zoom);
+        let area = Self::graph_area(size);
+        let theme =
+            crate::GraphTheme::from_theme(theme, axes, area).with_overrides(&self.theme_file);
+
+        // x-axis steps are picked in the display unit, then mapped back to counts/ms
+        let k = self.unit.factor() as f32;
+        let speed_step = nice_step(axes.width * k / Self::SPEED_TICKS) / k;
+        let sens_step = nice_step(axes.height / Self::SENS_TICKS);
+        let major_step = Size::new(speed_step, sens_step);
+        let minor_step = major_step * (1. / 5.);
         let transform = Transform2D::scale(area.width / axes.width, area.height / axes.height)
             .then_translate(Vector2D::new(area.x, area.y));
 
-        let mut frame = Frame::new(renderer, bounds.size());
+        if self.overlays.minor_grid {
+            let minor = Self::grid(axes, minor_step).transform(&transform);
+            painter.stroke(&minor, theme.minor_grid_stroke);
+        }
+        if self.overlays.grid {
+            let major = Self::grid(axes, major_step).transform(&transform);
+            painter.stroke(&major, theme.grid_stroke);
+        }
+        if self.overlays.reference_line && axes.height >= 1. {
+            let unity = Path::line(
+                Point { x: 0., y: 1. },
+                Point {
+                    x: axes.width,
+                    y: 1.,
+                },
+            )
+            .transform(&transform);
+            painter.stroke(&unity, theme.reference_stroke);
+        }
 
-        let input_speed = (read_input_speed() as f32).clamp(0., axes.width);
+        if let Some(percentiles) = self.percentiles.filter(|_| self.overlays.percentiles) {
+            let mut lower = 0.;
+            for ((upper, label), fill) in percentiles
+                .into_iter()
+                .zip(["p50", "p90", "p99"])
+                .zip(theme.percentile_fills)
+            {
+                let upper = upper.min(axes.width);
+                if upper > lower {
+                    let band = Path::rectangle(
+                        Point { x: lower, y: 0. },
+                        Size::new(upper - lower, axes.height),
+                    )
+                    .transform(&transform);
+                    painter.fill(&band, fill);
+                    let edge = Path::line(
+                        Point { x: upper, y: 0. },
+                        Point {
+                            x: upper,
+                            y: axes.height,
+                        },
+                    )
+                    .transform(&transform);
+                    painter.stroke(&edge, theme.percentile_stroke);
+                    painter.fill_text(Text {
+                        content: label.to_string(),
+                        ..(theme.percentile_label_text)(upper)
+                    });
+                }
+                lower = lower.max(upper);
+            }
+        }
+
+        let speed = self.speed.borrow();
+        let speedo = speed.speedometer();
+        let input_speed = speedo.current().clamp(0., axes.width);
         let (h_speedo, v_speedo) = {
             let mut h_bld = Builder::new();
             let mut v_bld = Builder::new();
@@ -114,8 +409,21 @@ impl<M, PS: ParamStore> Program<M> for Graph<PS> {
                 v_bld.build().transform(&transform),
             )
         };
-        frame.fill(&h_speedo, theme.h_speedo_fill);
-        frame.fill(&v_speedo, theme.v_speedo_fill);
+        painter.fill(&h_speedo, theme.h_speedo_fill);
+        painter.fill(&v_speedo, theme.v_speedo_fill);
+
+        let controls = Control::controls(self.context.clone());
+        let mut highlighted_handles = vec![];
+        if let Some(param) = self.highlight {
+            let (speeds, handles) = self.influence(param, &controls, axes.width);
+            let (from, to) = (speeds.start().max(0.), speeds.end().min(axes.width));
+            if from < to {
+                let band = Path::rectangle(Point::new(from, 0.), Size::new(to - from, axes.height))
+                    .transform(&transform);
+                painter.fill(&band, theme.highlight_fill);
+            }
+            highlighted_handles = handles;
+        }
 
         let (h_plot, v_plot) = {
             let mut h_bld = Builder::new();
@@ -126,8 +434,61 @@ impl<M, PS: ParamStore> Program<M> for Graph<PS> {
                 v_bld.build().transform(&transform),
             )
         };
-        frame.stroke(&h_plot, theme.h_plot_stroke);
-        frame.stroke(&v_plot, theme.v_plot_stroke);
+        painter.stroke(&h_plot, theme.h_plot_stroke);
+        painter.stroke(&v_plot, theme.v_plot_stroke);
+
+        if self.overlays.speed_history {
+            let peak = speedo.peak();
+            if (0. ..=axes.width).contains(&peak) && peak > 0. {
+                let (x_sens, y_sens) = self.sens_at(peak);
+                let peak_line = Path::line(
+                    Point { x: peak, y: 0. },
+                    Point {
+                        x: peak,
+                        y: x_sens.max(y_sens).min(axes.height),
+                    },
+                )
+                .transform(&transform);
+                painter.stroke(&peak_line, theme.peak_stroke);
+            }
+
+            for (speed, alpha) in speedo.trail() {
+                if !(0. ..=axes.width).contains(&speed) {
+                    continue;
+                }
+                let (x_sens, y_sens) = self.sens_at(speed);
+                for sens in [x_sens, y_sens] {
+                    if (0. ..=axes.height).contains(&sens) {
+                        let center = Point {
+                            x: speed * area.width / axes.width + area.x,
+                            y: sens * area.height / axes.height + area.y,
+                        };
+                        painter.fill(
+                            &Path::circle(center, theme.trail_radius),
+                            Color {
+                                a: theme.trail_color.a * alpha,
+                                ..theme.trail_color
+                            },
+                        );
+                    }
+                }
+            }
+
+            let bin_width = area.width / axes.width;
+            for (bin, density) in speedo.histogram().enumerate() {
+                if bin as f32 >= axes.width || density <= 0. {
+                    continue;
+                }
+                painter.fill_rectangle(
+                    area.position() + Vector::new(bin as f32 * bin_width, theme.histogram_offset),
+                    Size::new(bin_width, theme.histogram_height),
+                    Color {
+                        a: theme.histogram_color.a * density,
+                        ..theme.histogram_color
+                    },
+                );
+            }
+        }
 
         let x_axis = Path::line(
             area.position() + Vector::new(-10., 0.),
@@ -137,31 +498,221 @@ impl<M, PS: ParamStore> Program<M> for Graph<PS> {
             area.position() + Vector::new(0., 10.),
             area.position() + Vector::new(0., area.height),
         );
-        frame.stroke(&x_axis, theme.x_axis_stroke);
-        frame.stroke(&y_axis, theme.y_axis_stroke);
+        painter.stroke(&x_axis, theme.x_axis_stroke);
+        painter.stroke(&y_axis, theme.y_axis_stroke);
 
-        let controls = Control::controls(self.context.clone());
-        let mut x_labels = (1..=axes.width as u32 / 10)
-            .map(|u| u as f32 * 10.)
-            .collect::<Vec<_>>();
-        let mut y_labels = (1..=axes.height as u32 * 2)
-            .map(|u| u as f32 / 2.)
-            .collect::<Vec<_>>();
-        x_labels.extend(controls.iter().map(|c| c.location().x));
-        y_labels.extend(controls.iter().map(|c| c.location().y));
-        x_labels.retain(|f| (0. ..=axes.width).contains(f));
-        y_labels.retain(|f| (0. ..=axes.height).contains(f));
-        x_labels.sort_by(f32::total_cmp);
-        y_labels.sort_by(f32::total_cmp);
-        x_labels.dedup();
-        y_labels.dedup();
-        for f in x_labels {
-            frame.fill_text((theme.x_label_text)(f));
-        }
-        for f in y_labels {
-            frame.fill_text((theme.y_label_text)(f));
+        for (step, len, stroke) in [
+            (minor_step, theme.minor_tick_length, theme.minor_tick_stroke),
+            (major_step, theme.major_tick_length, theme.major_tick_stroke),
+        ] {
+            let mut bld = Builder::new();
+            for x in Self::steps(step.width, axes.width) {
+                let x = x * area.width / axes.width + area.x;
+                bld.move_to(Point { x, y: area.y });
+                bld.line_to(Point { x, y: area.y + len });
+            }
+            for y in Self::steps(step.height, axes.height) {
+                let y = y * area.height / axes.height + area.y;
+                bld.move_to(Point { x: area.x, y });
+                bld.line_to(Point { x: area.x - len, y });
+            }
+            painter.stroke(&bld.build(), stroke);
+        }
+        painter.fill_text(Text {
+            content: format!("input speed ({})", self.unit),
+            ..theme.x_title_text.clone()
+        });
+        painter.fill_text(theme.y_title_text.clone());
+
+        let to_screen = |p: Point| Point {
+            x: p.x * area.width / axes.width + area.x,
+            y: p.y * area.height / axes.height + area.y,
+        };
+        let label = |text: Text, value: f32, handle: Option<Point>| Label {
+            text: Text {
+                content: format_value(value),
+                color: handle.map_or(text.color, |_| theme.control_label_color),
+                font: handle.map_or(text.font, |_| theme.control_label_font),
+                ..text
+            },
+            handle,
+        };
+        let x_labels = controls
+            .iter()
+            .map(|c| c.location())
+            .filter(|p| p.x > 0. && p.x <= axes.width)
+            .map(|p| label((theme.x_label_text)(p.x), p.x * k, Some(to_screen(p))))
+            .chain(
+                Self::steps(major_step.width, axes.width)
+                    .map(|f| label((theme.x_label_text)(f), f * k, None)),
+            )
+            .collect();
+        let y_labels = controls
+            .iter()
+            .map(|c| c.location())
+            .filter(|p| p.y > 0. && p.y <= axes.height)
+            .map(|p| label((theme.y_label_text)(p.y), p.y, Some(to_screen(p))))
+            .chain(
+                Self::steps(major_step.height, axes.height)
+                    .map(|f| label((theme.y_label_text)(f), f, None)),
+            )
+            .collect();
+
+        for (labels, nudge, horizontal) in [
+            (x_labels, Vector::new(0., -theme.control_label_nudge), false),
+            (y_labels, Vector::new(theme.control_label_nudge, 0.), true),
+        ] {
+            for Label { text, handle } in labels::layout(labels, nudge) {
+                if let Some(handle) = handle {
+                    let foot = if horizontal {
+                        Point::new(area.x, handle.y)
+                    } else {
+                        Point::new(handle.x, area.y)
+                    };
+                    painter.stroke(&Path::line(foot, handle), theme.leader_stroke);
+                }
+                painter.fill_text(text);
+            }
+        }
+
+        let in_axes =
+            |p: Point| (0. ..=axes.width).contains(&p.x) && (0. ..=axes.height).contains(&p.y);
+        for p in controls
+            .iter()
+            .map(Control::location)
+            .filter(|&p| in_axes(p))
+        {
+            painter.fill(&Path::circle(to_screen(p), 3.), theme.control_label_color);
+        }
+        for &p in highlighted_handles.iter().filter(|&&p| in_axes(p)) {
+            painter.stroke(&Path::circle(to_screen(p), 7.), theme.highlight_stroke);
         }
 
+        // top right, clear of the y-axis title in the top left
+        for (i, (name, stroke)) in [
+            ("x sensitivity", theme.h_plot_stroke),
+            ("y sensitivity", theme.v_plot_stroke),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let y = area.y + area.height + 10. + i as f32 * 14.;
+            let x = area.x + area.width - 10.;
+            painter.stroke(
+                &Path::line(Point::new(x - 16., y), Point::new(x, y)),
+                stroke,
+            );
+            painter.fill_text(Text {
+                content: name.to_string(),
+                position: Point::new(x - 22., y),
+                horizontal_alignment: Horizontal::Right,
+                vertical_alignment: Vertical::Center,
+                ..theme.crosshair_text.clone()
+            });
+        }
+
+        if let Some(p) = hover {
+            let speed = (p.x - area.x) * axes.width / area.width;
+            let sens = (p.y - area.y) * axes.height / area.height;
+            if (0. ..=axes.width).contains(&speed) && (0. ..=axes.height).contains(&sens) {
+                let mut bld = Builder::new();
+                bld.move_to(Point::new(p.x, area.y));
+                bld.line_to(Point::new(p.x, area.y + area.height));
+                bld.move_to(Point::new(area.x, p.y));
+                bld.line_to(Point::new(area.x + area.width, p.y));
+                painter.stroke(&bld.build(), theme.crosshair_stroke);
+
+                let (x_sens, y_sens) = self.sens_at(speed);
+                let sens = if x_sens == y_sens {
+                    format!("{x_sens:.3}×")
+                } else {
+                    format!("x {x_sens:.3}×, y {y_sens:.3}×")
+                };
+                let right_half = p.x > area.x + area.width / 2.;
+                painter.fill_text(Text {
+                    content: format!("{} {}: {sens}", format_value(speed * k), self.unit),
+                    position: p + Vector::new(if right_half { -8. } else { 8. }, -8.),
+                    horizontal_alignment: if right_half {
+                        Horizontal::Right
+                    } else {
+                        Horizontal::Left
+                    },
+                    ..theme.crosshair_text.clone()
+                });
+            }
+        }
+    }
+}
+
+impl<M, PS: ParamStore, IS: InputSpeedSource> Program<M> for Graph<PS, IS> {
+    type State = ();
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        cursor: Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        self.paint(&mut frame, theme, bounds.size(), cursor.position_in(bounds));
         vec![frame.into_geometry()]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{IdleSpeed, RuntimeStore};
+    use maccel_core::{ALL_PARAMS, TuiContext};
+
+    /// Keeps the text drawn, ignoring everything else.
+    #[derive(Default)]
+    struct TextLog(Vec<String>);
+
+    impl Painter for TextLog {
+        fn fill(&mut self, _: &Path, _: impl Into<iced::widget::canvas::Fill>) {}
+        fn stroke<'a>(&mut self, _: &Path, _: impl Into<iced::widget::canvas::Stroke<'a>>) {}
+        fn fill_text(&mut self, text: impl Into<Text>) {
+            self.0.push(text.into().content);
+        }
+    }
+
+    #[test]
+    fn ranges_include_both_ends() {
+        let range = CurveRange::parse("0", "1", "0.1").unwrap();
+        assert_eq!(range.last_index(), 10);
+        assert_eq!(CurveRange::default().last_index(), 320);
+        assert_eq!(CurveRange::parse("2", "2", "1").unwrap().last_index(), 0);
+    }
+
+    #[test]
+    fn oversized_ranges_are_rejected() {
+        assert!(CurveRange::parse("0", "80", "1e-7").is_err());
+        assert!(CurveRange::parse("-3e38", "3e38", "1").is_err());
+        assert!(CurveRange::parse("0", "80", "-1").is_err());
+        assert!(CurveRange::parse("0", "inf", "1").is_err());
+        let unchecked = CurveRange {
+            from: 0.,
+            to: 80.,
+            step: 1e-9,
+        };
+        assert_eq!(unchecked.last_index(), CurveRange::MAX_SAMPLES - 1);
+    }
+
+    #[test]
+    fn sensitivity_labels_follow_the_zoom() {
+        let context = ContextRef::new(TuiContext::new(RuntimeStore::new(), ALL_PARAMS));
+        let labels = |zoom| {
+            let mut log = TextLog::default();
+            Graph::new(context.clone(), SpeedFeed::shared(IdleSpeed))
+                .with_zoom(zoom)
+                .paint(&mut log, &Theme::Dark, Size::new(800., 600.), None);
+            log.0
+        };
+        assert!(labels(1.).contains(&"0.5".to_string()));
+        let zoomed = labels(Graph::<RuntimeStore, IdleSpeed>::ZOOM_LIMITS.1);
+        assert!(zoomed.contains(&"0.05".to_string()));
+        assert!(!zoomed.contains(&"0.5".to_string()));
+    }
+}
@@ -1,8 +1,10 @@
+use crate::theme_file::{FillSpec, GraphThemeFile, StrokeSpec};
 use iced::alignment::{Horizontal, Vertical};
+use iced::font::Weight;
 use iced::widget::canvas::fill::Rule;
 use iced::widget::canvas::gradient::Linear;
 use iced::widget::canvas::{Fill, Gradient, LineCap, LineDash, LineJoin, Stroke, Style, Text};
-use iced::{Color, Pixels, Point, Rectangle, Size, Vector, color};
+use iced::{Color, Font, Pixels, Point, Rectangle, Size, Theme, Vector, color};
 
 pub struct GraphTheme {
     pub h_plot_stroke: Stroke<'static>,
@@ -11,8 +13,34 @@ pub struct GraphTheme {
     pub v_speedo_fill: Fill,
     pub x_axis_stroke: Stroke<'static>,
     pub y_axis_stroke: Stroke<'static>,
+    pub peak_stroke: Stroke<'static>,
+    pub trail_color: Color,
+    pub trail_radius: f32,
+    pub histogram_color: Color,
+    pub histogram_offset: f32,
+    pub histogram_height: f32,
+    pub percentile_fills: [Fill; 3],
+    pub percentile_stroke: Stroke<'static>,
+    pub percentile_label_text: Box<dyn Fn(f32) -> Text>,
+    pub grid_stroke: Stroke<'static>,
+    pub minor_grid_stroke: Stroke<'static>,
+    pub reference_stroke: Stroke<'static>,
+    pub major_tick_stroke: Stroke<'static>,
+    pub minor_tick_stroke: Stroke<'static>,
+    pub major_tick_length: f32,
+    pub minor_tick_length: f32,
+    pub x_title_text: Text,
+    pub y_title_text: Text,
+    pub crosshair_stroke: Stroke<'static>,
+    pub crosshair_text: Text,
+    pub control_label_color: Color,
+    pub control_label_font: Font,
+    pub control_label_nudge: f32,
+    pub leader_stroke: Stroke<'static>,
     pub x_label_text: Box<dyn Fn(f32) -> Text>,
     pub y_label_text: Box<dyn Fn(f32) -> Text>,
+    pub highlight_fill: Fill,
+    pub highlight_stroke: Stroke<'static>,
 }
 
 impl GraphTheme {
@@ -110,6 +138,155 @@ impl GraphTheme {
                     offset: 0,
                 },
             },
+            peak_stroke: Stroke {
+                style: Style::Solid(color!(0xffff00, 0.8)),
+                width: 2.,
+                line_cap: LineCap::Butt,
+                line_join: LineJoin::Miter,
+                line_dash: LineDash {
+                    segments: &[4., 2.],
+                    offset: 0,
+                },
+            },
+            trail_color: color!(0xffff00, 0.9),
+            trail_radius: 3.,
+            histogram_color: color!(0xffa000),
+            histogram_offset: 18.,
+            histogram_height: 6.,
+            percentile_fills: [0.16, 0.1, 0.05].map(|a| Fill {
+                style: Style::Solid(color!(0x40a0ff, a)),
+                rule: Rule::NonZero,
+            }),
+            percentile_stroke: Stroke {
+                style: Style::Solid(color!(0x40a0ff, 0.6)),
+                width: 1.,
+                line_cap: LineCap::Butt,
+                line_join: LineJoin::Miter,
+                line_dash: LineDash {
+                    segments: &[2., 3.],
+                    offset: 0,
+                },
+            },
+            percentile_label_text: Box::new(move |f| Text {
+                position: Point {
+                    x: f * area.width / axes.width + area.x,
+                    y: area.y + area.height - 8.,
+                },
+                color: color!(0x40a0ff),
+                size: Pixels(10.),
+                horizontal_alignment: Horizontal::Center,
+                vertical_alignment: Vertical::Center,
+                ..Text::default()
+            }),
+            grid_stroke: Stroke {
+                style: Style::Solid(color!(0xffffff, 0.15)),
+                width: 1.,
+                line_cap: LineCap::Butt,
+                line_join: LineJoin::Miter,
+                line_dash: LineDash {
+                    segments: &[],
+                    offset: 0,
+                },
+            },
+            minor_grid_stroke: Stroke {
+                style: Style::Solid(color!(0xffffff, 0.05)),
+                width: 1.,
+                line_cap: LineCap::Butt,
+                line_join: LineJoin::Miter,
+                line_dash: LineDash {
+                    segments: &[],
+                    offset: 0,
+                },
+            },
+            reference_stroke: Stroke {
+                style: Style::Solid(color!(0xffffff, 0.5)),
+                width: 1.,
+                line_cap: LineCap::Butt,
+                line_join: LineJoin::Miter,
+                line_dash: LineDash {
+                    segments: &[6., 4.],
+                    offset: 0,
+                },
+            },
+            major_tick_stroke: Stroke {
+                style: Style::Solid(Color::WHITE),
+                width: 1.,
+                line_cap: LineCap::Butt,
+                line_join: LineJoin::Miter,
+                line_dash: LineDash {
+                    segments: &[],
+                    offset: 0,
+                },
+            },
+            minor_tick_stroke: Stroke {
+                style: Style::Solid(color!(0xffffff, 0.6)),
+                width: 1.,
+                line_cap: LineCap::Butt,
+                line_join: LineJoin::Miter,
+                line_dash: LineDash {
+                    segments: &[],
+                    offset: 0,
+                },
+            },
+            major_tick_length: 5.,
+            minor_tick_length: 3.,
+            x_title_text: Text {
+                content: "input speed (counts/ms)".to_string(),
+                position: Point {
+                    x: area.x + area.width,
+                    y: area.y + 32.,
+                },
+                color: Color::WHITE,
+                size: Pixels(11.),
+                horizontal_alignment: Horizontal::Right,
+                vertical_alignment: Vertical::Center,
+                ..Text::default()
+            },
+            y_title_text: Text {
+                content: "sensitivity ×".to_string(),
+                position: Point {
+                    x: area.x + 8.,
+                    y: area.y + area.height,
+                },
+                color: Color::WHITE,
+                size: Pixels(11.),
+                horizontal_alignment: Horizontal::Left,
+                vertical_alignment: Vertical::Top,
+                ..Text::default()
+            },
+            crosshair_stroke: Stroke {
+                style: Style::Solid(color!(0xffffff, 0.4)),
+                width: 1.,
+                line_cap: LineCap::Butt,
+                line_join: LineJoin::Miter,
+                line_dash: LineDash {
+                    segments: &[3., 3.],
+                    offset: 0,
+                },
+            },
+            crosshair_text: Text {
+                color: Color::WHITE,
+                size: Pixels(12.),
+                horizontal_alignment: Horizontal::Left,
+                vertical_alignment: Vertical::Bottom,
+                ..Text::default()
+            },
+            control_label_color: color!(0xffff00),
+            control_label_font: Font {
+                weight: Weight::Bold,
+                ..Font::DEFAULT
+            },
+            control_label_nudge: 14.,
+            leader_stroke: Stroke {
+                style: Style::Solid(color!(0xffff00, 0.4)),
+                width: 1.,
+                line_cap: LineCap::Butt,
+                line_join: LineJoin::Miter,
+                line_dash: LineDash {
+                    segments: &[2., 2.],
+                    offset: 0,
+                },
+            },
             x_label_text: Box::new(move |f| Text {
                 content: f.to_string(),
                 position: Point {
@@ -134,6 +311,149 @@ impl GraphTheme {
                 vertical_alignment: Vertical::Center,
                 ..Text::default()
             }),
+            highlight_fill: Fill {
+                style: Style::Solid(color!(0xffff00, 0.12)),
+                rule: Rule::NonZero,
+            },
+            highlight_stroke: Stroke {
+                style: Style::Solid(color!(0xffff00)),
+                width: 2.,
+                line_cap: LineCap::Round,
+                line_join: LineJoin::Round,
+                line_dash: LineDash {
+                    segments: &[],
+                    offset: 0,
+                },
+            },
+        }
+    }
+
+    /// The default layout, coloured from the extended palette of an iced [`Theme`].
+    pub fn from_theme(theme: &Theme, axes: Size, area: Rectangle) -> Self {
+        let palette = theme.extended_palette();
+        let text = palette.background.base.text;
+        let accent = palette.primary.base.color;
+        let gradient = |to: Vector, stops: [(f32, Color); 2]| {
+            Style::Gradient(Gradient::Linear(
+                Linear::new(area.position(), area.position() + to)
+                    .add_stop(stops[0].0, stops[0].1)
+                    .add_stop(stops[1].0, stops[1].1),
+            ))
+        };
+        let horizontal = Vector::new(area.width, 0.);
+        let vertical = Vector::new(0., area.height);
+        let axis = [
+            (0., palette.success.base.color),
+            (1., palette.danger.base.color),
+        ];
+        let speedo = [
+            (0., Color { a: 0.1, ..accent }),
+            (0.5, Color { a: 0.5, ..accent }),
+        ];
+
+        let mut graph = GraphTheme::default(axes, area);
+        graph.h_plot_stroke.style = Style::Solid(text);
+        graph.v_plot_stroke.style = Style::Solid(text);
+        graph.h_speedo_fill.style = gradient(horizontal, speedo);
+        graph.v_speedo_fill.style = gradient(horizontal, speedo);
+        graph.x_axis_stroke.style = gradient(horizontal, axis);
+        graph.y_axis_stroke.style = gradient(vertical, axis);
+        graph.peak_stroke.style = Style::Solid(Color {
+            a: 0.8,
+            ..palette.danger.strong.color
+        });
+        graph.trail_color = Color { a: 0.9, ..accent };
+        graph.histogram_color = palette.primary.strong.color;
+
+        let band = palette.secondary.base.color;
+        for (fill, a) in graph.percentile_fills.iter_mut().zip([0.16, 0.1, 0.05]) {
+            fill.style = Style::Solid(Color { a, ..band });
+        }
+        graph.percentile_stroke.style = Style::Solid(Color { a: 0.6, ..band });
+
+        let line = palette.background.strong.color;
+        graph.grid_stroke.style = Style::Solid(Color { a: 0.5, ..line });
+        graph.minor_grid_stroke.style = Style::Solid(Color { a: 0.2, ..line });
+        graph.reference_stroke.style = Style::Solid(Color { a: 0.7, ..text });
+        graph.major_tick_stroke.style = Style::Solid(text);
+        graph.minor_tick_stroke.style = Style::Solid(Color { a: 0.6, ..text });
+        graph.crosshair_stroke.style = Style::Solid(Color { a: 0.4, ..text });
+        graph.crosshair_text.color = text;
+        graph.control_label_color = accent;
+        graph.leader_stroke.style = Style::Solid(Color { a: 0.4, ..accent });
+        graph.highlight_fill.style = Style::Solid(Color { a: 0.12, ..accent });
+        graph.highlight_stroke.style = Style::Solid(accent);
+        graph.x_title_text.color = text;
+        graph.y_title_text.color = text;
+
+        let recolor =
+            move |label: Box<dyn Fn(f32) -> Text>, color: Color| -> Box<dyn Fn(f32) -> Text> {
+                Box::new(move |f| Text { color, ..label(f) })
+            };
+        graph.x_label_text = recolor(graph.x_label_text, text);
+        graph.y_label_text = recolor(graph.y_label_text, text);
+        graph.percentile_label_text =
+            recolor(graph.percentile_label_text, palette.secondary.strong.color);
+        graph
+    }
+
+    /// Applies the fields set in a user theme file on top of this theme.
+    pub fn with_overrides(mut self, file: &GraphThemeFile) -> Self {
+        fn stroke(stroke: &mut Stroke<'static>, spec: StrokeSpec) {
+            if let Some(color) = spec.color {
+                stroke.style = Style::Solid(color);
+            }
+            if let Some(width) = spec.width {
+                stroke.width = width;
+            }
+        }
+        fn fill(fill: &mut Fill, spec: FillSpec) {
+            if let Some(color) = spec.color {
+                fill.style = Style::Solid(color);
+            }
+        }
+
+        stroke(&mut self.h_plot_stroke, file.h_plot);
+        stroke(&mut self.v_plot_stroke, file.v_plot);
+        stroke(&mut self.x_axis_stroke, file.x_axis);
+        stroke(&mut self.y_axis_stroke, file.y_axis);
+        fill(&mut self.h_speedo_fill, file.h_speedo);
+        fill(&mut self.v_speedo_fill, file.v_speedo);
+        stroke(&mut self.peak_stroke, file.peak);
+        self.trail_color = file.trail.color.unwrap_or(self.trail_color);
+        self.histogram_color = file.histogram.color.unwrap_or(self.histogram_color);
+        stroke(&mut self.percentile_stroke, file.percentiles);
+        stroke(&mut self.grid_stroke, file.grid);
+        stroke(&mut self.minor_grid_stroke, file.minor_grid);
+        stroke(&mut self.reference_stroke, file.reference);
+        stroke(&mut self.major_tick_stroke, file.ticks);
+        stroke(&mut self.minor_tick_stroke, file.ticks);
+        if let Some(color) = file.percentiles.color {
+            for (band, a) in self.percentile_fills.iter_mut().zip([0.16, 0.1, 0.05]) {
+                band.style = Style::Solid(Color { a, ..color });
+            }
+        }
+
+        let labels = file.labels;
+        if labels != Default::default() {
+            let restyle = move |label: Box<dyn Fn(f32) -> Text>| -> Box<dyn Fn(f32) -> Text> {
+                Box::new(move |f| {
+                    let text = label(f);
+                    Text {
+                        color: labels.color.unwrap_or(text.color),
+                        size: labels.size.map_or(text.size, Pixels),
+                        ..text
+                    }
+                })
+            };
+            self.x_label_text = restyle(self.x_label_text);
+            self.y_label_text = restyle(self.y_label_text);
+            self.percentile_label_text = restyle(self.percentile_label_text);
+            for title in [&mut self.x_title_text, &mut self.y_title_text] {
+                title.color = labels.color.unwrap_or(title.color);
+                title.size = labels.size.map_or(title.size, Pixels);
+            }
         }
+        self
     }
 }
@@ -1,9 +1,13 @@
-use crate::Message;
+use crate::focus_watch::FocusWatch;
+use crate::mode::mode_name;
+use crate::{Message, Overlay, Overlays, ParamMeta, ReplayReport, SpeedUnit};
 use iced::alignment::Horizontal;
 use iced::border::Radius;
+use iced::mouse::ScrollDelta;
 use iced::widget::canvas::Program;
 use iced::widget::{
-    Space, canvas, center, column, container, keyed_column, row, scrollable, text, text_input,
+    Space, button, canvas, center, checkbox, column, container, keyed_column, mouse_area, opaque,
+    pick_list, row, scrollable, slider, stack, text, text_input, tooltip,
 };
 use iced::{Alignment, Border, Element, Length, Theme};
 use maccel_core::{
@@ -12,21 +16,68 @@ use maccel_core::{
 };
 
 pub trait Gui: 'static {
-    fn param_box(param: Param, buf: &str) -> Element<'static, Message> {
-        container(
+    fn param_box(
+        param: Param,
+        buf: &str,
+        value: f64,
+        unit: SpeedUnit,
+    ) -> Element<'static, Message> {
+        let meta = ParamMeta::of(param);
+        let label = if SpeedUnit::applies_to(param) {
+            format!("{} ({unit})", param.display_name())
+        } else if meta.unit.is_empty() {
+            param.display_name().to_string()
+        } else {
+            format!("{} ({})", param.display_name(), meta.unit)
+        };
+        let boxed = container(
             column![
-                text(param.display_name())
-                    .align_x(Horizontal::Center)
-                    .width(Length::Fill),
                 row![
+                    text(label).align_x(Horizontal::Center).width(Length::Fill),
+                    button(text("Reset").size(12.))
+                        .on_press(Message::ResetParam(param))
+                        .style(button::text),
+                ]
+                .align_y(Alignment::Center),
+                row![
+                    Space::with_width(Length::FillPortion(1)),
+                    button(text("−").align_x(Horizontal::Center))
+                        .on_press(Message::StepParam(param, -1.))
+                        .style(button::secondary),
+                    mouse_area(FocusWatch::new(
+                        text_input(param.name(), buf)
+                            .id(param.name())
+                            .on_input(move |s| Message::FieldInput(param, s))
+                            .on_submit(Message::FieldUpdate(param))
+                            .padding(5.)
+                            .align_x(Horizontal::Left)
+                            .width(Length::FillPortion(4)),
+                        move |focused| Message::FieldFocus(param, focused),
+                    ))
+                    .on_scroll(move |delta| {
+                        let ((x, y), per_line) = match delta {
+                            ScrollDelta::Lines { x, y } => ((x, y), 1.),
+                            ScrollDelta::Pixels { x, y } => ((x, y), 20.),
+                        };
+                        // Shift+wheel turns into horizontal scrolling on some platforms
+                        let lines = if y != 0. { y } else { x };
+                        Message::StepParam(param, lines / per_line)
+                    }),
+                    button(text("+").align_x(Horizontal::Center))
+                        .on_press(Message::StepParam(param, 1.))
+                        .style(button::secondary),
                     Space::with_width(Length::FillPortion(1)),
-                    text_input(param.name(), buf)
-                        .id(param.name())
-                        .on_input(move |s| Message::FieldInput(param, s))
-                        .on_submit(Message::FieldUpdate(param))
-                        .padding(5.)
-                        .align_x(Horizontal::Left)
-                        .width(Length::FillPortion(4)),
+                ]
+                .spacing(5.)
+                .align_y(Alignment::Center),
+                row![
+                    Space::with_width(Length::FillPortion(1)),
+                    slider(meta.range(), value.clamp(meta.min, meta.max), move |v| {
+                        Message::SliderInput(param, v)
+                    })
+                    .step(meta.step)
+                    .on_release(Message::SliderReleased)
+                    .width(Length::FillPortion(4)),
                     Space::with_width(Length::FillPortion(1)),
                 ],
             ]
@@ -40,8 +91,19 @@ pub trait Gui: 'static {
             },
             ..container::Style::default()
         })
-        .padding([15., 0.])
-        .into()
+        .padding([15., 0.]);
+        let boxed = tooltip(
+            boxed,
+            container(text(meta.description).size(12.))
+                .padding(8.)
+                .max_width(260.)
+                .style(container::rounded_box),
+            tooltip::Position::Right,
+        );
+        mouse_area(boxed)
+            .on_enter(Message::HoverParam(Some(param)))
+            .on_exit(Message::HoverParam(None))
+            .into()
     }
     fn wrapper_style(theme: &Theme) -> container::Style {
         container::Style {
@@ -53,21 +115,41 @@ pub trait Gui: 'static {
             ..container::Style::default()
         }
     }
-    fn params_div(mode: AccelMode, bufs: &[String]) -> Element<'static, Message> {
+    fn params_div(
+        mode: AccelMode,
+        bufs: &[String],
+        values: &[f64],
+        unit: SpeedUnit,
+    ) -> Element<'static, Message> {
+        let switcher = row![
+            button("‹").on_press(Message::PrevMode).style(button::text),
+            text(format!("{} mode", mode_name(mode)))
+                .align_x(Horizontal::Center)
+                .width(Length::Fill),
+            button("›").on_press(Message::NextMode).style(button::text),
+        ]
+        .align_y(Alignment::Center);
         center(scrollable(
-            keyed_column(
-                ALL_COMMON_PARAMS
-                    .iter()
-                    .chain(match mode {
-                        AccelMode::Linear => ALL_LINEAR_PARAMS,
-                        AccelMode::Natural => ALL_NATURAL_PARAMS,
-                        AccelMode::Synchronous => ALL_SYNCHRONOUS_PARAMS,
-                    })
-                    .map(|&p| (p, Self::param_box(p, &bufs[p as usize]))),
-            )
-            .spacing(20.)
-            .align_items(Alignment::Center)
-            .width(Length::Fill),
+            column![
+                switcher,
+                keyed_column(
+                    ALL_COMMON_PARAMS
+                        .iter()
+                        .chain(match mode {
+                            AccelMode::Linear => ALL_LINEAR_PARAMS,
+                            AccelMode::Natural => ALL_NATURAL_PARAMS,
+                            AccelMode::Synchronous => ALL_SYNCHRONOUS_PARAMS,
+                        })
+                        .map(|&p| {
+                            let i = p as usize;
+                            (p, Self::param_box(p, &bufs[i], values[i], unit))
+                        }),
+                )
+                .spacing(20.)
+                .align_items(Alignment::Center)
+                .width(Length::Fill),
+            ]
+            .spacing(20.),
         ))
         .style(Self::wrapper_style)
         .padding(20.)
@@ -75,19 +157,213 @@ pub trait Gui: 'static {
         .height(Length::Fill)
         .into()
     }
+    fn toggle_button(
+        label: &'static str,
+        active_label: &'static str,
+        active: bool,
+        msg: Message,
+    ) -> Element<'static, Message> {
+        button(if active { active_label } else { label })
+            .on_press(msg)
+            .style(if active {
+                button::danger
+            } else {
+                button::secondary
+            })
+            .into()
+    }
+    fn replay_summary(report: &ReplayReport) -> String {
+        format!(
+            "replayed {:.0} counts: old curve {:.0} x {:.0}, new curve {:.0} x {:.0} ({:+.1}%)",
+            report.input,
+            report.old.0,
+            report.old.1,
+            report.new.0,
+            report.new.1,
+            report.change() * 100.,
+        )
+    }
+    fn toolbar(state: ToolbarState) -> Element<'static, Message> {
+        row![
+            Self::toggle_button(
+                "Record speeds",
+                "Stop recording",
+                state.recording_histogram,
+                Message::ToggleRecording,
+            ),
+            Self::toggle_button(
+                "Record trace",
+                "Stop trace",
+                state.recording_trace,
+                Message::ToggleTraceRecording,
+            ),
+            button("Replay trace")
+                .on_press_maybe((!state.recording_trace).then_some(Message::ReplayTrace))
+                .style(button::secondary),
+            Self::toggle_button(
+                "Cursor path",
+                "Back to curve",
+                state.path_preview,
+                Message::TogglePathPreview,
+            ),
+            button(if state.path_from_trace {
+                "Path: recorded speeds"
+            } else {
+                "Path: synthetic"
+            })
+            .on_press_maybe(state.path_preview.then_some(Message::TogglePathSource))
+            .style(button::secondary),
+            pick_list(SpeedUnit::ALL, Some(state.unit), Message::SelectUnit),
+            pick_list(Theme::ALL, Some(state.theme.clone()), Message::SelectTheme),
+            button(if state.theme.extended_palette().is_dark {
+                "Light"
+            } else {
+                "Dark"
+            })
+            .on_press(Message::ToggleDarkMode)
+            .style(button::secondary),
+            button("Reset mode")
+                .on_press(Message::ResetMode)
+                .style(button::secondary),
+            button("Undo")
+                .on_press_maybe(state.can_undo.then_some(Message::Undo))
+                .style(button::secondary),
+            button("Make persistent")
+                .on_press(Message::MakePersistent)
+                .style(button::secondary),
+            button("Export image")
+                .on_press_maybe(
+                    (!state.path_preview && !state.exporting).then_some(Message::ExportImage)
+                )
+                .style(button::secondary),
+            text(
+                state
+                    .status
+                    .or_else(|| state.replay.as_ref().map(Self::replay_summary))
+                    .unwrap_or_default()
+            ),
+        ]
+        .push_maybe(state.offer_helper.then(|| {
+            button("Use privileged helper")
+                .on_press(Message::UseHelper)
+                .style(button::danger)
+        }))
+        .spacing(5.)
+        .align_y(Alignment::Center)
+        .into()
+    }
+    /// The active shortcuts, shown over the graph.
+    fn key_help(bindings: Vec<(String, &'static str)>) -> Element<'static, Message> {
+        let rows = bindings.into_iter().map(|(keys, label)| {
+            row![text(keys).width(160.), text(label)]
+                .spacing(10.)
+                .into()
+        });
+        container(
+            column![
+                text("Keyboard shortcuts").size(18.),
+                column(rows).spacing(4.)
+            ]
+            .spacing(8.),
+        )
+        .style(|theme: &Theme| container::Style {
+            background: Some(theme.palette().background.into()),
+            ..Self::wrapper_style(theme)
+        })
+        .padding(10.)
+        .into()
+    }
+    /// The changes "Make persistent" is about to write, with a way out.
+    fn persist_review(changes: Vec<String>) -> Element<'static, Message> {
+        let lines = column(changes.into_iter().map(|change| text(change).into())).spacing(2.);
+        container(
+            column![
+                text("Persist these changes for the next module load?"),
+                lines,
+                row![
+                    button("Write config").on_press(Message::ConfirmPersist),
+                    button("Cancel")
+                        .on_press(Message::CancelPersist)
+                        .style(button::secondary),
+                ]
+                .spacing(5.),
+            ]
+            .spacing(5.),
+        )
+        .style(Self::wrapper_style)
+        .padding(5.)
+        .width(Length::Fill)
+        .into()
+    }
+    fn overlay_toggles(overlays: Overlays) -> Element<'static, Message> {
+        row(Overlay::ALL.into_iter().map(|overlay| {
+            checkbox(overlay.label(), overlays.get(overlay))
+                .on_toggle(move |on| Message::SetOverlay(overlay, on))
+                .into()
+        }))
+        .spacing(10.)
+        .into()
+    }
+    fn csv_controls(range: &[String; 3]) -> Element<'static, Message> {
+        let field = |i: usize, placeholder: &str| {
+            text_input(placeholder, &range[i])
+                .on_input(move |s| Message::CsvRangeInput(i, s))
+                .width(60.)
+        };
+        row![
+            text("CSV speeds (counts/ms)"),
+            field(0, "from"),
+            field(1, "to"),
+            field(2, "step"),
+            button("Export CSV")
+                .on_press(Message::ExportCsv)
+                .style(button::secondary),
+        ]
+        .spacing(5.)
+        .align_y(Alignment::Center)
+        .into()
+    }
     fn graph_div(graph: impl Program<Message> + 'static) -> Element<'static, Message> {
-        center(canvas(graph).width(Length::Fill).height(Length::Fill))
+        let plot =
+            mouse_area(canvas(graph).width(Length::Fill).height(Length::Fill)).on_scroll(|delta| {
+                let (ScrollDelta::Lines { y, .. } | ScrollDelta::Pixels { y, .. }) = delta;
+                let per_line = if matches!(delta, ScrollDelta::Pixels { .. }) {
+                    20.
+                } else {
+                    1.
+                };
+                Message::ZoomGraph(y / per_line)
+            });
+        center(plot)
             .style(Self::wrapper_style)
-            .width(Length::FillPortion(3))
+            .width(Length::Fill)
             .into()
     }
     fn screen(
-        graph: impl Program<Message> + 'static,
+        plot: Element<'static, Message>,
         bufs: &[String],
+        values: &[f64],
+        toolbar: ToolbarState,
     ) -> Element<'static, Message> {
+        let overlays = row![
+            Self::overlay_toggles(toolbar.overlays),
+            Space::with_width(Length::Fill),
+            Self::csv_controls(&toolbar.csv_range),
+        ]
+        .align_y(Alignment::Center);
+        let persist = toolbar.persist_changes.clone().map(Self::persist_review);
+        let plot = match toolbar.help.clone() {
+            Some(bindings) => stack![plot, opaque(center(Self::key_help(bindings)))].into(),
+            None => plot,
+        };
         row![
-            Self::params_div(AccelMode::Linear, bufs),
-            Self::graph_div(graph),
+            Self::params_div(toolbar.mode, bufs, values, toolbar.unit),
+            column![Self::toolbar(toolbar)]
+                .push_maybe(persist)
+                .push(overlays)
+                .push(plot)
+                .spacing(5.)
+                .width(Length::FillPortion(3)),
         ]
         .spacing(5.)
         .padding(5.)
@@ -95,6 +371,34 @@ pub trait Gui: 'static {
     }
 }
 
+/// What the toolbar above the graph needs to know about the app.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ToolbarState {
+    pub recording_histogram: bool,
+    pub recording_trace: bool,
+    pub replay: Option<ReplayReport>,
+    pub path_preview: bool,
+    pub path_from_trace: bool,
+    pub theme: Theme,
+    pub overlays: Overlays,
+    pub unit: SpeedUnit,
+    /// Outcome of the last action that has no other visible result.
+    pub status: Option<String>,
+    /// Unparsed from, to and step of the CSV export range.
+    pub csv_range: [String; 3],
+    /// A write failed for lack of permission and the helper could fix it.
+    pub offer_helper: bool,
+    pub mode: AccelMode,
+    /// There are param changes to undo.
+    pub can_undo: bool,
+    /// An image export is still being written.
+    pub exporting: bool,
+    /// Pending "Make persistent" changes awaiting confirmation.
+    pub persist_changes: Option<Vec<String>>,
+    /// Keys and what they do, while the shortcut help is open.
+    pub help: Option<Vec<(String, &'static str)>>,
+}
+
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
 pub struct DefaultGui;
 
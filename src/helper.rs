@@ -0,0 +1,490 @@
+use crate::dir_store::{MODE_FILE, encode, mode_from_index, mode_index};
+use crate::{BootConfig, DirStore, PersistPlan};
+use anyhow::{Context, bail};
+use maccel_core::{ALL_PARAMS, AccelMode, Param, fixedptc::Fpt, persist::ParamStore};
+use std::env::{current_exe, var_os};
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write as _};
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// Where the driver exposes its parameters.
+pub const SYSFS_PARAMS: &str = "/sys/module/maccel/parameters";
+
+/// True if `err` was caused by missing write permission.
+pub fn is_permission_error(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        cause
+            .downcast_ref::<io::Error>()
+            .is_some_and(|e| e.kind() == io::ErrorKind::PermissionDenied)
+    })
+}
+
+/// The helper socket in a private directory under `$XDG_RUNTIME_DIR`.
+///
+/// There is deliberately no fallback to a shared directory like `/tmp`,
+/// where another user could plant a socket of their own.
+pub fn default_socket() -> Option<PathBuf> {
+    var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .map(|dir| dir.join("maccel-gui").join("helper.sock"))
+}
+
+/// Fails unless `dir` is owned by `uid` and closed to everyone else.
+fn check_private_dir(dir: &Path, uid: u32) -> anyhow::Result<()> {
+    let meta = fs::metadata(dir).with_context(|| format!("failed reading {}", dir.display()))?;
+    if meta.uid() != uid || meta.mode() & 0o077 != 0 {
+        bail!(
+            "{} must be a directory only user {uid} can access",
+            dir.display()
+        );
+    }
+    Ok(())
+}
+
+/// Creates the socket's directory, private to the current user.
+fn private_socket_dir(socket: &Path) -> anyhow::Result<()> {
+    let dir = socket.parent().context("helper socket has no directory")?;
+    if !dir.exists() {
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)
+            .with_context(|| format!("failed creating {}", dir.display()))?;
+    }
+    check_private_dir(dir, current_uid())
+}
+
+fn current_uid() -> u32 {
+    // SAFETY: getuid has no preconditions and cannot fail
+    unsafe { libc::getuid() }
+}
+
+/// The uid of the process on the other end of `stream`.
+fn peer_uid(stream: &UnixStream) -> io::Result<u32> {
+    let mut cred = libc::ucred {
+        pid: 0,
+        uid: 0,
+        gid: 0,
+    };
+    let mut len = size_of::<libc::ucred>() as libc::socklen_t;
+    // SAFETY: `cred` and `len` describe a buffer of the size SO_PEERCRED writes
+    let res = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            (&raw mut cred).cast(),
+            &mut len,
+        )
+    };
+    if res == 0 {
+        Ok(cred.uid)
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// What the helper writes to. Under pkexec the paths on its command line
+/// come from the unprivileged user, so they are ignored in favour of the
+/// driver's parameters and the real boot config; they only take effect when
+/// running unprivileged, as in tests.
+fn helper_targets(
+    under_pkexec: bool,
+    params_dir: &Path,
+    boot_config: &BootConfig,
+) -> (PathBuf, BootConfig) {
+    if under_pkexec {
+        (PathBuf::from(SYSFS_PARAMS), BootConfig::default())
+    } else {
+        (params_dir.to_path_buf(), boot_config.clone())
+    }
+}
+
+/// Runs the privileged side: accepts one client on `socket` and writes its
+/// requests into `params_dir`, or `boot_config`, until it disconnects.
+///
+/// Only the user who asked for the helper may talk to it: `PKEXEC_UID`
+/// under pkexec, otherwise whoever owns the socket's directory. That
+/// directory must be private to them, and clients are checked by their
+/// peer credentials; anyone else gets an `ERR` reply and is hung up on.
+///
+/// Requests are single lines, `SET <param> <fixed-point value>` or
+/// `MODE <index>`, or `PERSIST <name>=<value>...` for the modprobe options,
+/// each answered with `OK` or `ERR <reason>`. Only known parameter files and
+/// the one options file are ever written.
+pub fn serve(socket: &Path, params_dir: &Path, boot_config: &BootConfig) -> anyhow::Result<()> {
+    let dir = socket.parent().context("helper socket has no directory")?;
+    let pkexec_uid = var_os("PKEXEC_UID");
+    let (params_dir, boot_config) = helper_targets(pkexec_uid.is_some(), params_dir, boot_config);
+    let owner = match pkexec_uid {
+        Some(uid) => uid
+            .to_str()
+            .and_then(|s| s.parse().ok())
+            .context("PKEXEC_UID is not a uid")?,
+        None => fs::metadata(dir)
+            .with_context(|| format!("failed reading {}", dir.display()))?
+            .uid(),
+    };
+    check_private_dir(dir, owner)?;
+
+    let _ = fs::remove_file(socket);
+    let listener = UnixListener::bind(socket)
+        .with_context(|| format!("failed binding {}", socket.display()))?;
+    fs::set_permissions(socket, fs::Permissions::from_mode(0o600))?;
+    std::os::unix::fs::lchown(socket, Some(owner), None)?;
+
+    let stream = loop {
+        let (mut stream, _) = listener
+            .accept()
+            .context("failed accepting helper client")?;
+        let reason = match peer_uid(&stream) {
+            Ok(uid) if uid == owner => break stream,
+            Ok(uid) => format!("this helper belongs to uid {owner}, not {uid}"),
+            Err(e) => format!("failed checking client credentials: {e}"),
+        };
+        let _ = writeln!(stream, "ERR {reason}");
+    };
+    let _ = fs::remove_file(socket);
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let reply = match handle(&line?, &params_dir, &boot_config) {
+            Ok(()) => "OK".to_string(),
+            Err(e) => format!("ERR {e:#}"),
+        };
+        writeln!(writer, "{reply}")?;
+    }
+    Ok(())
+}
+
+fn handle(request: &str, params_dir: &Path, boot_config: &BootConfig) -> anyhow::Result<()> {
+    if let Some(options) = request.strip_prefix("PERSIST ") {
+        return boot_config.write_options(options);
+    }
+    let (file, value) = match request.split_whitespace().collect::<Vec<_>>()[..] {
+        ["SET", name, value] => {
+            let param = ALL_PARAMS
+                .iter()
+                .find(|p| p.name() == name)
+                .with_context(|| format!("unknown param {name:?}"))?;
+            let value = value.parse().context("value is not a fixed-point number")?;
+            (param.name(), value)
+        }
+        ["MODE", index] => {
+            let index = index.parse().ok().filter(|&i| mode_from_index(i).is_some());
+            (MODE_FILE, index.context("invalid mode")? as i64)
+        }
+        _ => bail!("malformed request {request:?}"),
+    };
+    DirStore::new(params_dir).write(file, value)
+}
+
+/// The unprivileged side of [`serve`].
+#[derive(Debug)]
+pub struct HelperClient {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+}
+
+impl HelperClient {
+    pub fn connect(socket: &Path) -> anyhow::Result<Self> {
+        let writer = UnixStream::connect(socket)
+            .with_context(|| format!("failed connecting to {}", socket.display()))?;
+        let reader = BufReader::new(writer.try_clone()?);
+        Ok(HelperClient { reader, writer })
+    }
+
+    /// Starts the helper through `pkexec` and waits for it to listen.
+    pub fn launch(
+        socket: &Path,
+        params_dir: &Path,
+        boot_config: &BootConfig,
+    ) -> anyhow::Result<Self> {
+        const TIMEOUT: Duration = Duration::from_secs(60);
+        let mut child: Child = Command::new("pkexec")
+            .arg(current_exe()?)
+            .arg("--helper")
+            .arg(socket)
+            .arg(params_dir)
+            .arg(boot_config.root())
+            .spawn()
+            .context("failed starting pkexec")?;
+        let start = Instant::now();
+        loop {
+            if let Some(status) = child.try_wait()? {
+                bail!("privileged helper exited ({status})");
+            }
+            if let Ok(client) = Self::connect(socket) {
+                return Ok(client);
+            }
+            if start.elapsed() > TIMEOUT {
+                let _ = child.kill();
+                bail!("timed out waiting for the privileged helper");
+            }
+            sleep(Duration::from_millis(100));
+        }
+    }
+
+    fn request(&mut self, request: &str) -> anyhow::Result<()> {
+        writeln!(self.writer, "{request}").context("helper went away")?;
+        let mut reply = String::new();
+        self.reader
+            .read_line(&mut reply)
+            .context("helper went away")?;
+        match reply.trim_end() {
+            "OK" => Ok(()),
+            "" => bail!("helper went away"),
+            reply => bail!("helper: {}", reply.trim_start_matches("ERR ")),
+        }
+    }
+    pub fn set(&mut self, param: Param, value: f64) -> anyhow::Result<()> {
+        self.request(&format!("SET {} {}", param.name(), encode(value)))
+    }
+    pub fn set_mode(&mut self, mode: AccelMode) -> anyhow::Result<()> {
+        self.request(&format!("MODE {}", mode_index(mode)))
+    }
+    pub fn persist(&mut self, plan: &PersistPlan) -> anyhow::Result<()> {
+        self.request(&format!("PERSIST {}", plan.options()))
+    }
+}
+
+/// Where to find or how to start the helper; cheap to hand to a worker
+/// thread, since starting it waits on the user's password prompt.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HelperLauncher {
+    socket: Option<PathBuf>,
+    params_dir: PathBuf,
+    boot_config: BootConfig,
+}
+
+impl HelperLauncher {
+    pub fn new(socket: Option<PathBuf>, params_dir: impl Into<PathBuf>) -> Self {
+        HelperLauncher {
+            socket,
+            params_dir: params_dir.into(),
+            boot_config: BootConfig::default(),
+        }
+    }
+    pub fn with_boot_config(mut self, boot_config: BootConfig) -> Self {
+        self.boot_config = boot_config;
+        self
+    }
+
+    /// Connects to a running helper or starts one, blocking until it listens.
+    pub fn run(self) -> anyhow::Result<HelperClient> {
+        let socket = self.socket.context(
+            "XDG_RUNTIME_DIR is not set, so there is no private place for the helper socket",
+        )?;
+        private_socket_dir(&socket)?;
+        HelperClient::connect(&socket)
+            .or_else(|_| HelperClient::launch(&socket, &self.params_dir, &self.boot_config))
+    }
+}
+
+/// A store that writes directly until told to go through the helper.
+#[derive(Debug)]
+pub struct EscalatingStore<PS: ParamStore> {
+    direct: PS,
+    params_dir: PathBuf,
+    boot_config: BootConfig,
+    socket: Option<PathBuf>,
+    helper: Option<HelperClient>,
+}
+
+impl<PS: ParamStore> EscalatingStore<PS> {
+    pub fn new(direct: PS, params_dir: impl Into<PathBuf>) -> Self {
+        EscalatingStore {
+            direct,
+            params_dir: params_dir.into(),
+            boot_config: BootConfig::default(),
+            socket: default_socket(),
+            helper: None,
+        }
+    }
+    pub fn with_socket(mut self, socket: impl Into<PathBuf>) -> Self {
+        self.socket = Some(socket.into());
+        self
+    }
+    /// Where the helper may write the modprobe options.
+    pub fn with_boot_config(mut self, boot_config: BootConfig) -> Self {
+        self.boot_config = boot_config;
+        self
+    }
+    pub fn is_escalated(&self) -> bool {
+        self.helper.is_some()
+    }
+
+    pub fn launcher(&self) -> HelperLauncher {
+        HelperLauncher::new(self.socket.clone(), &self.params_dir)
+            .with_boot_config(self.boot_config.clone())
+    }
+    /// Sends all further writes through `helper`.
+    pub fn use_helper(&mut self, helper: HelperClient) {
+        self.helper = Some(helper);
+    }
+    /// Writes `plan` through the helper, or `None` if there is none yet.
+    pub fn persist(&mut self, plan: &PersistPlan) -> Option<anyhow::Result<()>> {
+        self.helper.as_mut().map(|helper| helper.persist(plan))
+    }
+    /// Sends all further writes through the helper, starting it if needed.
+    pub fn escalate(&mut self) -> anyhow::Result<()> {
+        if self.helper.is_none() {
+            let client = self.launcher().run()?;
+            self.use_helper(client);
+        }
+        Ok(())
+    }
+}
+
+impl<PS: ParamStore> ParamStore for EscalatingStore<PS> {
+    fn set(&mut self, param: Param, value: f64) -> anyhow::Result<()> {
+        match &mut self.helper {
+            Some(helper) => helper.set(param, value),
+            None => self.direct.set(param, value),
+        }
+    }
+    fn get(&self, param: Param) -> anyhow::Result<Fpt> {
+        self.direct.get(param)
+    }
+
+    fn set_current_accel_mode(&mut self, mode: AccelMode) -> anyhow::Result<()> {
+        match &mut self.helper {
+            Some(helper) => helper.set_mode(mode),
+            None => self.direct.set_current_accel_mode(mode),
+        }
+    }
+    fn get_current_accel_mode(&self) -> anyhow::Result<AccelMode> {
+        self.direct.get_current_accel_mode()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RuntimeStore, StoreWrite};
+    use std::thread::{self, JoinHandle};
+
+    /// A private socket path, a populated params dir and a config root,
+    /// fresh per test.
+    fn temp_dirs(name: &str) -> (PathBuf, PathBuf, BootConfig) {
+        let root = std::env::temp_dir()
+            .join(format!("maccel-gui-helper-{}", std::process::id()))
+            .join(name);
+        let _ = fs::remove_dir_all(&root);
+        let socket = root.join("run").join("helper.sock");
+        private_socket_dir(&socket).unwrap();
+        let params = root.join("params");
+        DirStore::populate(&params, AccelMode::Linear, [(Param::SensMult, 1.)]).unwrap();
+        (socket, params, BootConfig::new(root.join("root")))
+    }
+    /// Runs a stand-in helper as the current user and waits for it to listen.
+    fn spawn_helper(
+        socket: &Path,
+        params: &Path,
+        boot_config: &BootConfig,
+    ) -> JoinHandle<anyhow::Result<()>> {
+        let (s, p, b) = (
+            socket.to_path_buf(),
+            params.to_path_buf(),
+            boot_config.clone(),
+        );
+        let server = thread::spawn(move || serve(&s, &p, &b));
+        let start = Instant::now();
+        while !socket.exists() {
+            assert!(
+                start.elapsed() < Duration::from_secs(5),
+                "helper never listened"
+            );
+            sleep(Duration::from_millis(5));
+        }
+        server
+    }
+
+    #[test]
+    fn requests_are_answered() {
+        let (socket, params, boot_config) = temp_dirs("requests");
+        let server = spawn_helper(&socket, &params, &boot_config);
+        let mut client = HelperClient::connect(&socket).unwrap();
+        let store = DirStore::new(&params);
+
+        client.set(Param::SensMult, 2.5).unwrap();
+        assert_eq!(f64::from(store.get(Param::SensMult).unwrap()), 2.5);
+        client.set_mode(AccelMode::Natural).unwrap();
+        assert_eq!(store.get_current_accel_mode().unwrap(), AccelMode::Natural);
+
+        let err = |client: &mut HelperClient, request: &str| {
+            format!("{:#}", client.request(request).unwrap_err())
+        };
+        assert!(err(&mut client, "SET NOPE 1").contains("unknown param"));
+        let not_a_number = format!("SET {} one", Param::SensMult.name());
+        assert!(err(&mut client, &not_a_number).contains("fixed-point"));
+        assert!(err(&mut client, "MODE 9").contains("invalid mode"));
+        assert!(err(&mut client, "RM -rf /").contains("malformed"));
+        assert!(err(&mut client, "PERSIST FOO=1").contains("unknown option"));
+        assert!(!boot_config.path().exists());
+
+        drop(client);
+        server.join().unwrap().unwrap();
+        assert!(!socket.exists());
+    }
+
+    #[test]
+    fn escalated_store_writes_through_the_helper() {
+        let (socket, params, boot_config) = temp_dirs("escalate");
+        let direct = RuntimeStore::new();
+        let log = direct.write_log();
+        let mut store = EscalatingStore::new(direct, &params).with_socket(&socket);
+        store.set(Param::Accel, 0.25).unwrap();
+        assert_eq!(&*log.entries(), &[StoreWrite::Param(Param::Accel, 0.25)]);
+        let plan = boot_config
+            .plan(AccelMode::Synchronous, &[1.; ALL_PARAMS.len()])
+            .unwrap();
+        assert!(store.persist(&plan).is_none());
+
+        let server = spawn_helper(&socket, &params, &boot_config);
+        store.escalate().unwrap();
+        assert!(store.is_escalated());
+        store.set(Param::Accel, 0.5).unwrap();
+        store
+            .set_current_accel_mode(AccelMode::Synchronous)
+            .unwrap();
+        assert_eq!(log.entries().len(), 1);
+        let written = DirStore::new(&params);
+        assert_eq!(f64::from(written.get(Param::Accel).unwrap()), 0.5);
+        assert_eq!(
+            written.get_current_accel_mode().unwrap(),
+            AccelMode::Synchronous
+        );
+        store.persist(&plan).unwrap().unwrap();
+        assert_eq!(boot_config.read().unwrap().unwrap(), plan.content);
+
+        drop(store);
+        server.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn shared_socket_directories_are_refused() {
+        let (socket, params, boot_config) = temp_dirs("shared");
+        let dir = socket.parent().unwrap();
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o755)).unwrap();
+        let err = serve(&socket, &params, &boot_config).unwrap_err();
+        assert!(format!("{err:#}").contains("only user"));
+        assert!(HelperLauncher::new(Some(socket), &params).run().is_err());
+        assert!(HelperLauncher::new(None, &params).run().is_err());
+    }
+
+    #[test]
+    fn pkexec_ignores_the_callers_paths() {
+        let (_, params, boot_config) = temp_dirs("targets");
+        let pinned = helper_targets(true, &params, &boot_config);
+        assert_eq!(pinned, (PathBuf::from(SYSFS_PARAMS), BootConfig::default()));
+        let unprivileged = helper_targets(false, &params, &boot_config);
+        assert_eq!(unprivileged, (params, boot_config));
+    }
+}
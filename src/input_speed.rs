@@ -0,0 +1,210 @@
+use crate::{Speedometer, Trace};
+use maccel_core::inputspeed::{read_input_speed, setup_input_speed_reader};
+use std::cell::RefCell;
+use std::f64::consts::TAU;
+use std::fmt::Debug;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// Where the speedometer gets its input speed (counts/ms) from, sampled once per frame.
+pub trait InputSpeedSource: Debug + 'static {
+    fn read_speed(&mut self, now: Instant) -> f64;
+}
+
+/// A source and the [`Speedometer`] it feeds. The app samples it once per
+/// frame, and each [`Graph`](crate::Graph) drawn shows the speedometer.
+#[derive(Debug)]
+pub struct SpeedFeed<IS: InputSpeedSource> {
+    source: IS,
+    speedo: Speedometer,
+}
+
+impl<IS: InputSpeedSource> SpeedFeed<IS> {
+    pub fn new(source: IS) -> Self {
+        SpeedFeed {
+            source,
+            speedo: Speedometer::new(),
+        }
+    }
+    /// A new feed, ready to be shared between the app and its graphs.
+    pub fn shared(source: IS) -> Rc<RefCell<Self>> {
+        Rc::new(RefCell::new(SpeedFeed::new(source)))
+    }
+    /// Reads the source and records its speed.
+    pub fn sample(&mut self, now: Instant) -> f32 {
+        let speed = self.source.read_speed(now) as f32;
+        self.speedo.record(speed, now);
+        speed
+    }
+    /// Records a speed from elsewhere, such as a replayed trace, instead.
+    pub fn record(&mut self, speed: f32, now: Instant) {
+        self.speedo.record(speed, now);
+    }
+    pub fn speedometer(&self) -> &Speedometer {
+        &self.speedo
+    }
+}
+
+/// No input at all, for graphs of the curve alone.
+#[derive(Debug, Default)]
+pub struct IdleSpeed;
+
+impl InputSpeedSource for IdleSpeed {
+    fn read_speed(&mut self, _now: Instant) -> f64 {
+        0.
+    }
+}
+
+/// The speed reported by the kernel module.
+#[derive(Debug)]
+pub struct DriverSpeed;
+
+impl DriverSpeed {
+    pub fn new() -> Self {
+        setup_input_speed_reader();
+        DriverSpeed
+    }
+}
+
+impl Default for DriverSpeed {
+    fn default() -> Self {
+        DriverSpeed::new()
+    }
+}
+
+impl InputSpeedSource for DriverSpeed {
+    fn read_speed(&mut self, _now: Instant) -> f64 {
+        read_input_speed()
+    }
+}
+
+/// Slow sine sweeps across the axis, interrupted by random flicks.
+#[derive(Debug)]
+pub struct SyntheticSpeed {
+    start: Instant,
+    last_read: Option<Instant>,
+    burst: Option<(Instant, Duration, f64)>, // (start, length, peak)
+}
+
+impl SyntheticSpeed {
+    const SWEEP_PERIOD: f64 = 6.; // seconds
+    const SWEEP_PEAK: f64 = 30.; // counts/ms
+    const BURSTS_PER_SEC: f64 = 0.4;
+
+    pub fn new() -> Self {
+        SyntheticSpeed {
+            start: Instant::now(),
+            last_read: None,
+            burst: None,
+        }
+    }
+}
+
+impl Default for SyntheticSpeed {
+    fn default() -> Self {
+        SyntheticSpeed::new()
+    }
+}
+
+impl InputSpeedSource for SyntheticSpeed {
+    fn read_speed(&mut self, now: Instant) -> f64 {
+        let t = now.saturating_duration_since(self.start).as_secs_f64();
+        let sweep = Self::SWEEP_PEAK * (1. - (TAU * t / Self::SWEEP_PERIOD).cos()) / 2.;
+
+        if self
+            .burst
+            .is_some_and(|(at, len, _)| now.saturating_duration_since(at) > len)
+        {
+            self.burst = None;
+        }
+        // the chance of a burst starting since the last read, so the rate
+        // holds however often the source is read
+        let elapsed = self
+            .last_read
+            .map_or(0., |last| now.saturating_duration_since(last).as_secs_f64());
+        self.last_read = Some(now);
+        let chance = 1. - (-Self::BURSTS_PER_SEC * elapsed).exp();
+        if self.burst.is_none() && rand::random_bool(chance) {
+            self.burst = Some((
+                now,
+                Duration::from_millis(rand::random_range(150..500)),
+                rand::random_range(20. ..75.),
+            ));
+        }
+        let burst = self.burst.map_or(0., |(at, len, peak)| {
+            let phase = now.saturating_duration_since(at).as_secs_f64() / len.as_secs_f64();
+            peak * (phase * TAU / 2.).sin()
+        });
+
+        (sweep.max(burst) + rand::random_range(-0.5..0.5)).max(0.)
+    }
+}
+
+/// Plays back a recorded [`Trace`] in real time, looping unless created with [`ReplaySpeed::once`].
+#[derive(Debug)]
+pub struct ReplaySpeed {
+    trace: Trace,
+    start: Option<Instant>,
+    looping: bool,
+}
+
+impl ReplaySpeed {
+    pub fn new(trace: Trace) -> Self {
+        ReplaySpeed {
+            trace,
+            start: None,
+            looping: true,
+        }
+    }
+    pub fn once(trace: Trace) -> Self {
+        ReplaySpeed {
+            looping: false,
+            ..ReplaySpeed::new(trace)
+        }
+    }
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        Ok(ReplaySpeed::new(Trace::load(path)?))
+    }
+    pub fn is_finished(&self, now: Instant) -> bool {
+        !self.looping
+            && self
+                .start
+                .is_some_and(|start| now.saturating_duration_since(start) > self.trace.duration())
+    }
+}
+
+impl InputSpeedSource for ReplaySpeed {
+    fn read_speed(&mut self, now: Instant) -> f64 {
+        let start = *self.start.get_or_insert(now);
+        let length = self.trace.duration();
+        if length.is_zero() {
+            return 0.;
+        }
+        let elapsed = now.saturating_duration_since(start).as_nanos();
+        if !self.looping && elapsed > length.as_nanos() {
+            return 0.;
+        }
+        let elapsed = elapsed % length.as_nanos();
+        self.trace
+            .speed_at(Duration::from_nanos(elapsed as u64))
+            .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn burst_chance_follows_the_time_between_reads() {
+        let mut synthetic = SyntheticSpeed::new();
+        let start = synthetic.start;
+        synthetic.read_speed(start);
+        synthetic.read_speed(start);
+        assert_eq!(synthetic.burst, None);
+        // a burst is all but certain after a minute without reads
+        synthetic.read_speed(start + Duration::from_secs(60));
+        assert!(synthetic.burst.is_some());
+    }
+}
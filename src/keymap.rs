@@ -0,0 +1,387 @@
+use anyhow::{Context, bail};
+use iced::keyboard::{Key, Modifiers, key::Named};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Something a keyboard shortcut can do.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    NextField,
+    PrevField,
+    NextMode,
+    PrevMode,
+    /// Submits the focused field, as Enter in the field itself does.
+    Apply,
+    /// Nudge the focused field by one step.
+    Increase,
+    Decrease,
+    /// Nudge the focused field by ten steps.
+    IncreaseMore,
+    DecreaseMore,
+    ZoomIn,
+    ZoomOut,
+    ResetZoom,
+    ExportImage,
+    ExportCsv,
+    /// Restores the params changed by the last edit.
+    Undo,
+    /// Saves the mode and params as a new named profile.
+    SaveProfile,
+    /// Loads the saved profile after the last one loaded, wrapping around.
+    NextProfile,
+    ToggleHelp,
+}
+
+impl Action {
+    pub const ALL: [Action; 18] = [
+        Action::NextField,
+        Action::PrevField,
+        Action::NextMode,
+        Action::PrevMode,
+        Action::Apply,
+        Action::Increase,
+        Action::Decrease,
+        Action::IncreaseMore,
+        Action::DecreaseMore,
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::ResetZoom,
+        Action::ExportImage,
+        Action::ExportCsv,
+        Action::Undo,
+        Action::SaveProfile,
+        Action::NextProfile,
+        Action::ToggleHelp,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::NextField => "Next field",
+            Action::PrevField => "Previous field",
+            Action::NextMode => "Next mode",
+            Action::PrevMode => "Previous mode",
+            Action::Apply => "Apply the focused field",
+            Action::Increase => "Increase the focused field by a step",
+            Action::Decrease => "Decrease the focused field by a step",
+            Action::IncreaseMore => "Increase the focused field by ten steps",
+            Action::DecreaseMore => "Decrease the focused field by ten steps",
+            Action::ZoomIn => "Zoom in",
+            Action::ZoomOut => "Zoom out",
+            Action::ResetZoom => "Reset zoom",
+            Action::ExportImage => "Export image",
+            Action::ExportCsv => "Export CSV",
+            Action::Undo => "Undo the last parameter change",
+            Action::SaveProfile => "Save the parameters as a new profile",
+            Action::NextProfile => "Switch to the next saved profile",
+            Action::ToggleHelp => "Show or hide this help",
+        }
+    }
+}
+
+/// Names of the non-character keys a binding can use.
+const NAMED_KEYS: &[(&str, Named)] = &[
+    ("tab", Named::Tab),
+    ("enter", Named::Enter),
+    ("escape", Named::Escape),
+    ("space", Named::Space),
+    ("backspace", Named::Backspace),
+    ("delete", Named::Delete),
+    ("up", Named::ArrowUp),
+    ("down", Named::ArrowDown),
+    ("left", Named::ArrowLeft),
+    ("right", Named::ArrowRight),
+    ("home", Named::Home),
+    ("end", Named::End),
+    ("pageup", Named::PageUp),
+    ("pagedown", Named::PageDown),
+    ("f1", Named::F1),
+];
+
+const MODIFIER_NAMES: &[(&str, Modifiers)] = &[
+    ("ctrl", Modifiers::CTRL),
+    ("alt", Modifiers::ALT),
+    ("super", Modifiers::LOGO),
+    ("shift", Modifiers::SHIFT),
+];
+
+/// A key with the modifiers held down, written like `ctrl+shift+e`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct KeyBinding {
+    key: Key,
+    modifiers: Modifiers,
+}
+
+impl KeyBinding {
+    pub fn parse(s: &str) -> anyhow::Result<Self> {
+        let s = s.trim().to_lowercase();
+        // a trailing `+` is the plus key itself, as in `ctrl++`
+        let (mods, key) = match s.strip_suffix('+') {
+            Some(mods) => (mods.trim_end_matches('+'), "+"),
+            None => s.rsplit_once('+').unwrap_or(("", &s)),
+        };
+        let mut modifiers = Modifiers::empty();
+        for name in mods.split('+').filter(|m| !m.is_empty()) {
+            let Some(&(_, m)) = MODIFIER_NAMES.iter().find(|(n, _)| *n == name) else {
+                bail!("unknown modifier {name:?} in key binding {s:?}");
+            };
+            modifiers |= m;
+        }
+        let key = match NAMED_KEYS.iter().find(|(n, _)| *n == key) {
+            Some(&(_, named)) => Key::Named(named),
+            None if key.chars().count() == 1 => Key::Character(key.into()),
+            None => bail!("unknown key {key:?} in key binding {s:?}"),
+        };
+        Ok(KeyBinding { key, modifiers })
+    }
+
+    pub fn matches(&self, key: &Key, modifiers: Modifiers) -> bool {
+        let key = match key.as_ref() {
+            Key::Character(c) => Key::Character(c.to_lowercase().into()),
+            _ => key.clone(),
+        };
+        // symbols like `?` already need shift to be typed
+        let ignore_shift = matches!(
+            self.key.as_ref(),
+            Key::Character(c) if !c.chars().any(char::is_alphabetic)
+        );
+        let held = if ignore_shift {
+            modifiers - Modifiers::SHIFT
+        } else {
+            modifiers
+        };
+        key == self.key && held == self.modifiers
+    }
+
+    /// True if a text field uses the key to type, move the cursor, submit
+    /// or reach the clipboard.
+    pub fn conflicts_with_editing(&self) -> bool {
+        let command = self
+            .modifiers
+            .intersects(Modifiers::CTRL | Modifiers::ALT | Modifiers::LOGO);
+        match self.key.as_ref() {
+            Key::Character(c) => !command || matches!(c, "a" | "c" | "v" | "x" | "z"),
+            Key::Named(named) => matches!(
+                named,
+                Named::Space
+                    | Named::Enter
+                    | Named::Backspace
+                    | Named::Delete
+                    | Named::ArrowLeft
+                    | Named::ArrowRight
+                    | Named::Home
+                    | Named::End
+            ),
+            Key::Unidentified => false,
+        }
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for &(name, m) in MODIFIER_NAMES {
+            if self.modifiers.contains(m) {
+                write!(f, "{name}+")?;
+            }
+        }
+        match self.key.as_ref() {
+            Key::Named(named) => {
+                let name = NAMED_KEYS.iter().find(|(_, n)| *n == named);
+                write!(f, "{}", name.map_or("?", |(name, _)| name))
+            }
+            Key::Character(c) => write!(f, "{c}"),
+            Key::Unidentified => write!(f, "?"),
+        }
+    }
+}
+
+/// Keyboard shortcuts: the defaults, with any actions rebound in the
+/// user's keymap file replacing their default keys.
+///
+/// The file maps action names to lists of keys, e.g.
+/// `next_mode = ["ctrl+right"]`; an empty list unbinds the action.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Keymap {
+    bindings: Vec<(KeyBinding, Action)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        const DEFAULTS: &[(&str, Action)] = &[
+            ("tab", Action::NextField),
+            ("down", Action::NextField),
+            ("shift+tab", Action::PrevField),
+            ("up", Action::PrevField),
+            ("right", Action::NextMode),
+            ("left", Action::PrevMode),
+            ("ctrl+s", Action::Apply),
+            ("ctrl+up", Action::Increase),
+            ("ctrl+down", Action::Decrease),
+            ("pageup", Action::IncreaseMore),
+            ("pagedown", Action::DecreaseMore),
+            ("ctrl+=", Action::ZoomIn),
+            ("ctrl++", Action::ZoomIn),
+            ("ctrl+-", Action::ZoomOut),
+            ("ctrl+0", Action::ResetZoom),
+            ("ctrl+e", Action::ExportImage),
+            ("ctrl+shift+e", Action::ExportCsv),
+            ("ctrl+z", Action::Undo),
+            ("ctrl+shift+p", Action::SaveProfile),
+            ("ctrl+p", Action::NextProfile),
+            ("?", Action::ToggleHelp),
+            ("f1", Action::ToggleHelp),
+        ];
+        Keymap {
+            bindings: DEFAULTS
+                .iter()
+                .map(|&(key, action)| (KeyBinding::parse(key).unwrap(), action))
+                .collect(),
+        }
+    }
+}
+
+impl Keymap {
+    pub fn default_path() -> PathBuf {
+        crate::paths::config_dir().join("keymap.toml")
+    }
+
+    /// Applies the user's keymap at `path`, keeping the defaults if it is
+    /// missing or invalid; in the latter case the error comes back too, to
+    /// be shown to the user.
+    pub fn load_or_default(path: &Path) -> (Self, Option<anyhow::Error>) {
+        if !path.exists() {
+            return (Keymap::default(), None);
+        }
+        match Self::load(path) {
+            Ok(keymap) => (keymap, None),
+            Err(e) => (Keymap::default(), Some(e)),
+        }
+    }
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("failed reading {}", path.display()))?;
+        Self::default()
+            .with_overrides(&content)
+            .with_context(|| format!("failed parsing {}", path.display()))
+    }
+    pub fn with_overrides(mut self, toml: &str) -> anyhow::Result<Self> {
+        let overrides: HashMap<Action, Vec<String>> = toml::from_str(toml)?;
+        for (action, keys) in overrides {
+            self.bindings.retain(|&(_, a)| a != action);
+            for key in keys {
+                self.bindings.push((KeyBinding::parse(&key)?, action));
+            }
+        }
+        Ok(self)
+    }
+
+    /// The action bound to a key press, skipping bindings that would steal
+    /// keys from a field being edited.
+    pub fn lookup(&self, key: &Key, modifiers: Modifiers, editing: bool) -> Option<Action> {
+        self.bindings
+            .iter()
+            .filter(|(binding, _)| !(editing && binding.conflicts_with_editing()))
+            .find(|(binding, _)| binding.matches(key, modifiers))
+            .map(|&(_, action)| action)
+    }
+
+    /// Every action with the keys that currently trigger it, for the help overlay.
+    pub fn active(&self, editing: bool) -> Vec<(Action, Vec<String>)> {
+        Action::ALL
+            .into_iter()
+            .map(|action| {
+                let keys = self
+                    .bindings
+                    .iter()
+                    .filter(|&&(ref b, a)| a == action && !(editing && b.conflicts_with_editing()))
+                    .map(|(b, _)| b.to_string())
+                    .collect::<Vec<_>>();
+                (action, keys)
+            })
+            .filter(|(_, keys)| !keys.is_empty())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(s: &str) -> Key {
+        Key::Character(s.into())
+    }
+
+    #[test]
+    fn bindings_round_trip_through_text() {
+        for s in [
+            "ctrl+shift+e",
+            "shift+tab",
+            "?",
+            "ctrl++",
+            "pagedown",
+            "alt+f1",
+        ] {
+            assert_eq!(KeyBinding::parse(s).unwrap().to_string(), s);
+        }
+        assert!(KeyBinding::parse("hyper+x").is_err());
+        assert!(KeyBinding::parse("ctrl+nope").is_err());
+    }
+
+    #[test]
+    fn shift_only_matters_for_letters_and_named_keys() {
+        let keymap = Keymap::default();
+        assert_eq!(
+            keymap.lookup(&key("?"), Modifiers::SHIFT, false),
+            Some(Action::ToggleHelp)
+        );
+        assert_eq!(
+            keymap.lookup(&key("E"), Modifiers::CTRL | Modifiers::SHIFT, false),
+            Some(Action::ExportCsv)
+        );
+        assert_eq!(
+            keymap.lookup(&key("e"), Modifiers::CTRL, false),
+            Some(Action::ExportImage)
+        );
+    }
+
+    #[test]
+    fn editing_keeps_cursor_and_typing_keys() {
+        let keymap = Keymap::default();
+        let left = Key::Named(Named::ArrowLeft);
+        assert_eq!(
+            keymap.lookup(&left, Modifiers::empty(), false),
+            Some(Action::PrevMode)
+        );
+        assert_eq!(keymap.lookup(&left, Modifiers::empty(), true), None);
+        assert_eq!(keymap.lookup(&key("?"), Modifiers::SHIFT, true), None);
+        assert_eq!(
+            keymap.lookup(&Key::Named(Named::Tab), Modifiers::empty(), true),
+            Some(Action::NextField)
+        );
+        let help = keymap.active(true);
+        assert!(help.iter().all(|(a, _)| *a != Action::PrevMode));
+    }
+
+    #[test]
+    fn overrides_replace_an_actions_defaults() {
+        let keymap = Keymap::default()
+            .with_overrides("next_mode = [\"ctrl+right\"]\nexport_csv = []\n")
+            .unwrap();
+        let right = Key::Named(Named::ArrowRight);
+        assert_eq!(keymap.lookup(&right, Modifiers::empty(), false), None);
+        assert_eq!(
+            keymap.lookup(&right, Modifiers::CTRL, false),
+            Some(Action::NextMode)
+        );
+        assert!(
+            keymap
+                .active(false)
+                .iter()
+                .all(|(a, _)| *a != Action::ExportCsv)
+        );
+        assert!(Keymap::default().with_overrides("fly = [\"x\"]").is_err());
+    }
+}
@@ -0,0 +1,127 @@
+use iced::alignment::{Horizontal, Vertical};
+use iced::widget::canvas::Text;
+use iced::{Point, Rectangle, Size, Vector};
+
+/// An axis label waiting for a spot, optionally tied to a control handle.
+#[derive(Clone, Debug)]
+pub struct Label {
+    pub text: Text,
+    pub handle: Option<Point>,
+}
+
+/// Formats an axis value with at most two decimals, dropping trailing zeros.
+pub fn format_value(f: f32) -> String {
+    let s = format!("{f:.2}");
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    match s {
+        "-0" => "0".to_string(),
+        s => s.to_string(),
+    }
+}
+
+/// Rough extent of a label, assuming glyphs about 0.6em wide.
+fn bounds(text: &Text) -> Rectangle {
+    let size = Size::new(
+        text.content.chars().count() as f32 * text.size.0 * 0.6 + 2.,
+        text.size.0 + 2.,
+    );
+    let x = match text.horizontal_alignment {
+        Horizontal::Left => text.position.x,
+        Horizontal::Center => text.position.x - size.width / 2.,
+        Horizontal::Right => text.position.x - size.width,
+    };
+    let y = match text.vertical_alignment {
+        Vertical::Top => text.position.y,
+        Vertical::Center => text.position.y - size.height / 2.,
+        Vertical::Bottom => text.position.y - size.height,
+    };
+    Rectangle::new(Point::new(x, y), size)
+}
+
+/// Picks the labels that can be drawn without overlapping.
+///
+/// Control labels are placed first, moving by `nudge` when their usual spot
+/// is taken; tick labels are then kept only where there is still room.
+pub fn layout(labels: Vec<Label>, nudge: Vector) -> Vec<Label> {
+    let (controls, ticks): (Vec<_>, Vec<_>) = labels.into_iter().partition(|l| l.handle.is_some());
+    let mut placed: Vec<(Rectangle, Label)> = Vec::new();
+    let fits = |placed: &[(Rectangle, Label)], r: &Rectangle| {
+        placed
+            .iter()
+            .all(|(other, _)| other.intersection(r).is_none())
+    };
+
+    for mut label in controls {
+        if placed
+            .iter()
+            .any(|(_, l)| l.text.content == label.text.content && l.handle.is_some())
+        {
+            continue;
+        }
+        for _ in 0..2 {
+            let r = bounds(&label.text);
+            if fits(&placed, &r) {
+                placed.push((r, label));
+                break;
+            }
+            label.text.position = label.text.position + nudge;
+        }
+    }
+    for label in ticks {
+        let r = bounds(&label.text);
+        if fits(&placed, &r) {
+            placed.push((r, label));
+        }
+    }
+    placed.into_iter().map(|(_, l)| l).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn label(content: &str, x: f32, y: f32, control: bool) -> Label {
+        let position = Point::new(x, y);
+        Label {
+            text: Text {
+                content: content.to_string(),
+                position,
+                ..Text::default()
+            },
+            handle: control.then_some(position),
+        }
+    }
+    fn contents(labels: &[Label]) -> Vec<&str> {
+        labels.iter().map(|l| l.text.content.as_str()).collect()
+    }
+
+    #[test]
+    fn values_drop_trailing_zeros() {
+        assert_eq!(format_value(1.), "1");
+        assert_eq!(format_value(0.5), "0.5");
+        assert_eq!(format_value(0.126), "0.13");
+        assert_eq!(format_value(-0.001), "0");
+    }
+
+    #[test]
+    fn controls_are_nudged_and_ticks_give_way() {
+        let labels = vec![
+            label("2", 100., 0., false),
+            label("3", 300., 0., false),
+            label("1.5", 0., 0., true),
+            label("1.5", 200., 0., true),
+            label("0.5", 0., 0., true),
+            label("0.7", 0., 0., true),
+            label("1", 10., 0., false),
+        ];
+        let nudge = Vector::new(0., -20.);
+        let placed = layout(labels, nudge);
+
+        // a repeated control label is shown once and a third one at the same
+        // spot has nowhere left to go
+        assert_eq!(contents(&placed), ["1.5", "0.5", "2", "3"]);
+        assert_eq!(placed[0].text.position, Point::ORIGIN);
+        assert_eq!(placed[1].text.position, Point::ORIGIN + nudge);
+        assert!(placed[1].handle.is_some());
+    }
+}
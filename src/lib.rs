@@ -1,20 +1,121 @@
 mod app;
+mod boot_config;
 mod control;
+mod dir_store;
+mod export;
+mod focus_watch;
 mod graph;
 mod graph_theme;
 mod gui;
+mod helper;
+mod input_speed;
+mod keymap;
+mod labels;
 mod message;
 mod mock;
+mod mode;
+mod painter;
+mod param_meta;
+mod path_preview;
+mod paths;
+mod prefs;
+mod profile;
+mod recorder;
+mod speedo;
+mod theme_file;
+mod trace;
+mod units;
 
 pub use app::App;
+pub use boot_config::{BootConfig, MODPROBE_CONF, OptionChange, PersistPlan};
 pub use control::Control;
-pub use graph::Graph;
+pub use dir_store::DirStore;
+pub use export::ImageExport;
+pub use graph::{CurveRange, Graph, Overlay, Overlays};
 pub use graph_theme::GraphTheme;
-pub use gui::{DefaultGui, Gui};
-pub use message::Message;
-pub use mock::run_mock;
+pub use gui::{DefaultGui, Gui, ToolbarState};
+pub use helper::{
+    EscalatingStore, HelperClient, HelperLauncher, SYSFS_PARAMS, serve as serve_helper,
+};
+pub use input_speed::{
+    DriverSpeed, IdleSpeed, InputSpeedSource, ReplaySpeed, SpeedFeed, SyntheticSpeed,
+};
+pub use keymap::{Action, KeyBinding, Keymap};
+pub use message::{Effect, Message};
+pub use mock::{RuntimeStore, StoreWrite, WriteLog, run_mock, run_mock_store, run_mock_with};
+pub use painter::{Painter, SvgPainter};
+pub use param_meta::ParamMeta;
+pub use path_preview::{MouseDelta, PathPreview};
+pub use paths::AppPaths;
+pub use prefs::{Preferences, WindowPrefs};
+pub use profile::Profile;
+pub use recorder::SpeedHistogram;
+pub use speedo::Speedometer;
+pub use theme_file::{FillSpec, GraphThemeFile, LabelSpec, StrokeSpec, ThemeFileWatcher};
+pub use trace::{ReplayReport, Trace};
+pub use units::SpeedUnit;
 
 pub fn run_gui() -> iced::Result {
-    use maccel_core::{ALL_PARAMS, ContextRef, TuiContext, persist::SysFsStore};
-    App::new(ContextRef::new(TuiContext::new(SysFsStore, ALL_PARAMS))).run::<DefaultGui>()
+    use maccel_core::persist::SysFsStore;
+    match std::env::var_os(PARAMS_DIR_VAR) {
+        Some(dir) => run_gui_with(DirStore::new(&dir), dir),
+        None => run_gui_with(SysFsStore, SYSFS_PARAMS),
+    }
+}
+
+/// Points [`run_gui`] at a directory standing in for the driver's parameters.
+pub const PARAMS_DIR_VAR: &str = "MACCEL_GUI_PARAMS_DIR";
+/// Points "Make persistent" at a directory standing in for `/`.
+pub const CONFIG_ROOT_VAR: &str = "MACCEL_GUI_CONFIG_ROOT";
+
+fn run_gui_with<PS: maccel_core::persist::ParamStore + 'static>(
+    direct: PS,
+    params_dir: impl Into<std::path::PathBuf>,
+) -> iced::Result {
+    use maccel_core::{ALL_PARAMS, ContextRef, TuiContext};
+    let boot_config =
+        std::env::var_os(CONFIG_ROOT_VAR).map_or_else(BootConfig::default, BootConfig::new);
+    let store = EscalatingStore::new(direct, params_dir).with_boot_config(boot_config.clone());
+    App::new(
+        ContextRef::new(TuiContext::new(store, ALL_PARAMS)),
+        DriverSpeed::new(),
+    )
+    .with_escalation(
+        EscalatingStore::launcher,
+        EscalatingStore::use_helper,
+        EscalatingStore::persist,
+    )
+    .with_boot_config(boot_config)
+    .run::<DefaultGui>()
+}
+
+/// Writes CSV samples of the live driver curve to `out`, or stdout for `-`.
+///
+/// Reads the same parameters [`run_gui`] would, honouring [`PARAMS_DIR_VAR`].
+pub fn write_curve_csv(out: &std::path::Path, range: CurveRange) -> anyhow::Result<()> {
+    use maccel_core::persist::SysFsStore;
+    match std::env::var_os(PARAMS_DIR_VAR) {
+        Some(dir) => write_curve_csv_from(DirStore::new(dir), out, range),
+        None => write_curve_csv_from(SysFsStore, out, range),
+    }
+}
+
+fn write_curve_csv_from<PS: maccel_core::persist::ParamStore>(
+    store: PS,
+    out: &std::path::Path,
+    range: CurveRange,
+) -> anyhow::Result<()> {
+    use anyhow::Context;
+    use maccel_core::{ALL_PARAMS, ContextRef, TuiContext};
+    let graph = Graph::new(
+        ContextRef::new(TuiContext::new(store, ALL_PARAMS)),
+        SpeedFeed::shared(IdleSpeed),
+    );
+    let csv = export::curve_csv(&graph, range);
+    if out == std::path::Path::new("-") {
+        print!("{csv}");
+        Ok(())
+    } else {
+        std::fs::write(out, csv).with_context(|| format!("failed writing {}", out.display()))
+    }
 }
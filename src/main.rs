@@ -1,5 +1,47 @@
-use maccel_gui::run_mock;
+use maccel_gui::{
+    BootConfig, CurveRange, ReplaySpeed, SYSFS_PARAMS, run_mock, run_mock_with, serve_helper,
+    write_curve_csv,
+};
+use std::path::Path;
+
+const USAGE: &str = "usage: maccel-gui [TRACE]
+       maccel-gui --csv OUT [FROM TO STEP]
+       maccel-gui --helper SOCKET [PARAMS_DIR [CONFIG_ROOT]]";
+
+fn fail(e: impl std::fmt::Display) -> ! {
+    eprintln!("{e:#}");
+    std::process::exit(1);
+}
 
 fn main() -> iced::Result {
-    run_mock()
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.as_slice() {
+        [flag, out, range @ ..] if flag == "--csv" => {
+            let range = match range {
+                [] => CurveRange::default(),
+                [from, to, step] => CurveRange::parse(from, to, step).unwrap_or_else(|e| fail(e)),
+                _ => fail(USAGE),
+            };
+            write_curve_csv(Path::new(out), range).unwrap_or_else(|e| fail(e));
+            Ok(())
+        }
+        [flag, socket, dir @ ..] if flag == "--helper" => {
+            let (dir, boot_config) = match dir {
+                [] => (SYSFS_PARAMS, BootConfig::default()),
+                [dir] => (dir.as_str(), BootConfig::default()),
+                [dir, root] => (dir.as_str(), BootConfig::new(root)),
+                _ => fail(USAGE),
+            };
+            serve_helper(Path::new(socket), Path::new(dir), &boot_config)
+                .unwrap_or_else(|e| fail(e));
+            Ok(())
+        }
+        [flag, ..] if flag.starts_with('-') => fail(USAGE),
+        [trace] => match ReplaySpeed::open(Path::new(trace)) {
+            Ok(replay) => run_mock_with(replay),
+            Err(e) => fail(e),
+        },
+        [] => run_mock(),
+        _ => fail(USAGE),
+    }
 }
@@ -1,11 +1,74 @@
+use crate::{ImageExport, Overlay, SpeedUnit};
+use iced::keyboard::{Key, Modifiers};
+use iced::{Point, Size, Theme};
 use maccel_core::Param;
+use std::path::PathBuf;
+use std::time::Instant;
+
+/// What [`App::apply`](crate::App::apply) asks of the iced runtime.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Effect {
+    None,
+    Focus(Param),
+    /// Finish the export on a blocking thread, then send [`Message::ImageExported`].
+    ExportImage(ImageExport),
+}
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Message {
     FieldInput(Param, String),
     FieldUpdate(Param),
+    /// A field's text input gained or lost keyboard focus.
+    FieldFocus(Param, bool),
     NextField,
     PrevField,
     NextMode,
     PrevMode,
+    Tick(Instant),
+    ToggleRecording,
+    ToggleTraceRecording,
+    ReplayTrace,
+    TogglePathPreview,
+    TogglePathSource,
+    SelectTheme(Theme),
+    ToggleDarkMode,
+    SetOverlay(Overlay, bool),
+    SelectUnit(SpeedUnit),
+    ExportImage,
+    /// The files written by an image export, or why it failed.
+    ImageExported(Result<[PathBuf; 2], String>),
+    CsvRangeInput(usize, String),
+    ExportCsv,
+    SliderInput(Param, f64),
+    SliderReleased,
+    /// Moves a param by this many increments, scaled by the held modifiers.
+    StepParam(Param, f32),
+    /// Moves a param by this many increments, whatever modifiers are held.
+    NudgeParam(Param, f64),
+    ModifiersChanged(Modifiers),
+    /// A key press, resolved through the keymap, and whether a widget
+    /// already handled it.
+    KeyPressed(Key, Modifiers, bool),
+    ToggleHelp,
+    ResetParam(Param),
+    HoverParam(Option<Param>),
+    /// Retries writes through the privileged helper after a permission error.
+    UseHelper,
+    /// Shows what persisting the current parameters would change.
+    MakePersistent,
+    ConfirmPersist,
+    CancelPersist,
+    /// Zooms the graph in by this many scroll lines, out if negative.
+    ZoomGraph(f32),
+    ResetZoom,
+    WindowResized(Size),
+    WindowMoved(Point),
+    /// Restores defaults for the common params and the active mode's params.
+    ResetMode,
+    /// Restores the params changed by the last edit.
+    Undo,
+    /// Saves the mode and params as a new profile.
+    SaveProfile,
+    /// Loads the saved profile after the last one loaded.
+    NextProfile,
 }
@@ -1,42 +1,135 @@
-use crate::{App, DefaultGui};
+use crate::{App, DefaultGui, InputSpeedSource, ParamMeta, SyntheticSpeed};
+use anyhow::bail;
 use maccel_core::{
     ALL_PARAMS, AccelMode, ContextRef, Param, TuiContext, fixedptc::Fpt, persist::ParamStore,
 };
+use std::cell::{Ref, RefCell};
+use std::rc::Rc;
+use std::thread::sleep;
+use std::time::Duration;
 
+/// A write that reached a [`RuntimeStore`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StoreWrite {
+    Param(Param, f64),
+    Mode(AccelMode),
+}
+
+/// Shared view of the writes a [`RuntimeStore`] has accepted, still readable
+/// after the store has been moved into a context.
+#[derive(Clone, Debug, Default)]
+pub struct WriteLog(Rc<RefCell<Vec<StoreWrite>>>);
+
+impl WriteLog {
+    pub fn entries(&self) -> Ref<'_, [StoreWrite]> {
+        Ref::map(self.0.borrow(), Vec::as_slice)
+    }
+    pub fn clear(&self) {
+        self.0.borrow_mut().clear();
+    }
+    fn push(&self, write: StoreWrite) {
+        self.0.borrow_mut().push(write);
+    }
+}
+
+/// In-memory param store, starting from the driver's defaults.
 #[derive(Debug)]
-struct RuntimeStore(AccelMode, [f64; ALL_PARAMS.len()]);
+pub struct RuntimeStore {
+    mode: AccelMode,
+    values: [f64; ALL_PARAMS.len()],
+    failing: [bool; ALL_PARAMS.len()],
+    latency: Duration,
+    log: WriteLog,
+}
 
 impl Default for RuntimeStore {
     fn default() -> Self {
-        RuntimeStore(
-            AccelMode::Linear,
-            [1., 1., 1000., 0., 0., 0., 0., 0., 0., 0., 0., 0., 0.],
-        )
+        let mut values = [0.; ALL_PARAMS.len()];
+        for &param in ALL_PARAMS {
+            values[param as usize] = ParamMeta::of(param).default;
+        }
+        RuntimeStore {
+            mode: AccelMode::Linear,
+            values,
+            failing: [false; ALL_PARAMS.len()],
+            latency: Duration::ZERO,
+            log: WriteLog::default(),
+        }
+    }
+}
+
+impl RuntimeStore {
+    pub fn new() -> Self {
+        RuntimeStore::default()
+    }
+    pub fn with_mode(mut self, mode: AccelMode) -> Self {
+        self.mode = mode;
+        self
+    }
+    pub fn with_value(mut self, param: Param, value: f64) -> Self {
+        self.values[param as usize] = value;
+        self
+    }
+    /// Makes every write to `param` fail.
+    pub fn fail_on_set(mut self, param: Param) -> Self {
+        self.failing[param as usize] = true;
+        self
+    }
+    /// Delays every read and write, like a slow sysfs.
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+    pub fn write_log(&self) -> WriteLog {
+        self.log.clone()
+    }
+
+    fn wait(&self) {
+        if !self.latency.is_zero() {
+            sleep(self.latency);
+        }
     }
 }
 
 impl ParamStore for RuntimeStore {
     fn set(&mut self, param: Param, value: f64) -> anyhow::Result<()> {
-        self.1[param as usize] = value;
+        self.wait();
+        if self.failing[param as usize] {
+            bail!("injected failure writing {}", param.name());
+        }
+        self.values[param as usize] = value;
+        self.log.push(StoreWrite::Param(param, value));
         Ok(())
     }
     fn get(&self, param: Param) -> anyhow::Result<Fpt> {
-        Ok(self.1[param as usize].into())
+        self.wait();
+        Ok(self.values[param as usize].into())
     }
 
     fn set_current_accel_mode(&mut self, mode: AccelMode) -> anyhow::Result<()> {
-        self.0 = mode;
+        self.wait();
+        self.mode = mode;
+        self.log.push(StoreWrite::Mode(mode));
         Ok(())
     }
     fn get_current_accel_mode(&self) -> anyhow::Result<AccelMode> {
-        Ok(self.0)
+        self.wait();
+        Ok(self.mode)
     }
 }
 
 pub fn run_mock() -> iced::Result {
-    App::new(ContextRef::new(TuiContext::new(
-        RuntimeStore::default(),
-        ALL_PARAMS,
-    )))
+    run_mock_with(SyntheticSpeed::new())
+}
+
+pub fn run_mock_with(speed_source: impl InputSpeedSource) -> iced::Result {
+    run_mock_store(RuntimeStore::default(), speed_source)
+}
+
+pub fn run_mock_store(store: RuntimeStore, speed_source: impl InputSpeedSource) -> iced::Result {
+    App::new(
+        ContextRef::new(TuiContext::new(store, ALL_PARAMS)),
+        speed_source,
+    )
     .run::<DefaultGui>()
 }
@@ -0,0 +1,22 @@
+use maccel_core::AccelMode;
+
+pub(crate) const ALL_MODES: &[AccelMode] = &[
+    AccelMode::Linear,
+    AccelMode::Natural,
+    AccelMode::Synchronous,
+];
+
+pub(crate) fn mode_name(mode: AccelMode) -> &'static str {
+    match mode {
+        AccelMode::Linear => "linear",
+        AccelMode::Natural => "natural",
+        AccelMode::Synchronous => "synchronous",
+    }
+}
+
+pub(crate) fn parse_mode(s: &str) -> Option<AccelMode> {
+    ALL_MODES
+        .iter()
+        .copied()
+        .find(|&mode| mode_name(mode).eq_ignore_ascii_case(s))
+}
@@ -0,0 +1,228 @@
+use iced::alignment::{Horizontal, Vertical};
+use iced::font::Weight;
+use iced::widget::canvas::fill::Rule;
+use iced::widget::canvas::path::lyon_path::Event;
+use iced::widget::canvas::{Fill, Frame, Gradient, LineCap, LineJoin, Path, Stroke, Style, Text};
+use iced::{Color, Point, Size};
+use std::fmt::Write as _;
+
+/// The drawing operations [`Graph`](crate::Graph) needs, so the same
+/// geometry can go to the screen or to a file.
+pub trait Painter {
+    fn fill(&mut self, path: &Path, fill: impl Into<Fill>);
+    fn stroke<'a>(&mut self, path: &Path, stroke: impl Into<Stroke<'a>>);
+    fn fill_text(&mut self, text: impl Into<Text>);
+    fn fill_rectangle(&mut self, top_left: Point, size: Size, fill: impl Into<Fill>) {
+        self.fill(&Path::rectangle(top_left, size), fill);
+    }
+}
+
+impl Painter for Frame {
+    fn fill(&mut self, path: &Path, fill: impl Into<Fill>) {
+        Frame::fill(self, path, fill);
+    }
+    fn stroke<'a>(&mut self, path: &Path, stroke: impl Into<Stroke<'a>>) {
+        Frame::stroke(self, path, stroke);
+    }
+    fn fill_text(&mut self, text: impl Into<Text>) {
+        Frame::fill_text(self, text);
+    }
+    fn fill_rectangle(&mut self, top_left: Point, size: Size, fill: impl Into<Fill>) {
+        Frame::fill_rectangle(self, top_left, size, fill);
+    }
+}
+
+/// Writes everything painted into it as an SVG document.
+#[derive(Clone, Debug, Default)]
+pub struct SvgPainter {
+    size: Size,
+    defs: String,
+    body: String,
+    gradients: usize,
+}
+
+impl SvgPainter {
+    pub fn new(size: Size, background: Color) -> Self {
+        let mut svg = SvgPainter {
+            size,
+            ..SvgPainter::default()
+        };
+        svg.fill_rectangle(Point::ORIGIN, size, background);
+        svg
+    }
+    pub fn finish(self) -> String {
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n<defs>\n{}</defs>\n{}</svg>\n",
+            self.defs,
+            self.body,
+            w = self.size.width,
+            h = self.size.height,
+        )
+    }
+
+    fn path_data(path: &Path) -> String {
+        let mut d = String::new();
+        for event in path.raw().iter() {
+            let _ = match event {
+                Event::Begin { at } => write!(d, "M{} {} ", at.x, at.y),
+                Event::Line { to, .. } => write!(d, "L{} {} ", to.x, to.y),
+                Event::Quadratic { ctrl, to, .. } => {
+                    write!(d, "Q{} {} {} {} ", ctrl.x, ctrl.y, to.x, to.y)
+                }
+                Event::Cubic {
+                    ctrl1, ctrl2, to, ..
+                } => write!(
+                    d,
+                    "C{} {} {} {} {} {} ",
+                    ctrl1.x, ctrl1.y, ctrl2.x, ctrl2.y, to.x, to.y
+                ),
+                Event::End { close: true, .. } => write!(d, "Z "),
+                Event::End { close: false, .. } => Ok(()),
+            };
+        }
+        d
+    }
+    fn color(c: Color) -> (String, f32) {
+        let [r, g, b, _] = c.into_rgba8();
+        (format!("#{r:02x}{g:02x}{b:02x}"), c.a)
+    }
+    /// Returns the paint and opacity attribute values for a style.
+    fn paint(&mut self, style: Style) -> (String, f32) {
+        match style {
+            Style::Solid(c) => Self::color(c),
+            Style::Gradient(Gradient::Linear(linear)) => {
+                let id = format!("g{}", self.gradients);
+                self.gradients += 1;
+                let _ = writeln!(
+                    self.defs,
+                    "<linearGradient id=\"{id}\" gradientUnits=\"userSpaceOnUse\" x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\">",
+                    linear.start.x, linear.start.y, linear.end.x, linear.end.y,
+                );
+                for stop in linear.stops.into_iter().flatten() {
+                    let (color, opacity) = Self::color(stop.color);
+                    let _ = writeln!(
+                        self.defs,
+                        "<stop offset=\"{}\" stop-color=\"{color}\" stop-opacity=\"{opacity}\"/>",
+                        stop.offset,
+                    );
+                }
+                self.defs.push_str("</linearGradient>\n");
+                (format!("url(#{id})"), 1.)
+            }
+        }
+    }
+    fn escape(s: &str) -> String {
+        s.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+    }
+}
+
+impl Painter for SvgPainter {
+    fn fill(&mut self, path: &Path, fill: impl Into<Fill>) {
+        let fill = fill.into();
+        let (paint, opacity) = self.paint(fill.style);
+        let rule = match fill.rule {
+            Rule::NonZero => "nonzero",
+            Rule::EvenOdd => "evenodd",
+        };
+        let _ = writeln!(
+            self.body,
+            "<path d=\"{}\" fill=\"{paint}\" fill-opacity=\"{opacity}\" fill-rule=\"{rule}\"/>",
+            Self::path_data(path),
+        );
+    }
+    fn stroke<'a>(&mut self, path: &Path, stroke: impl Into<Stroke<'a>>) {
+        let stroke = stroke.into();
+        let (paint, opacity) = self.paint(stroke.style);
+        let cap = match stroke.line_cap {
+            LineCap::Butt => "butt",
+            LineCap::Square => "square",
+            LineCap::Round => "round",
+        };
+        let join = match stroke.line_join {
+            LineJoin::Miter => "miter",
+            LineJoin::Round => "round",
+            LineJoin::Bevel => "bevel",
+        };
+        let dash = if stroke.line_dash.segments.is_empty() {
+            String::new()
+        } else {
+            let segments = stroke
+                .line_dash
+                .segments
+                .iter()
+                .map(f32::to_string)
+                .collect::<Vec<_>>();
+            format!(
+                " stroke-dasharray=\"{}\" stroke-dashoffset=\"{}\"",
+                segments.join(" "),
+                stroke.line_dash.offset,
+            )
+        };
+        let _ = writeln!(
+            self.body,
+            "<path d=\"{}\" fill=\"none\" stroke=\"{paint}\" stroke-opacity=\"{opacity}\" stroke-width=\"{}\" stroke-linecap=\"{cap}\" stroke-linejoin=\"{join}\"{dash}/>",
+            Self::path_data(path),
+            stroke.width,
+        );
+    }
+    fn fill_text(&mut self, text: impl Into<Text>) {
+        let text = text.into();
+        let (color, opacity) = Self::color(text.color);
+        let anchor = match text.horizontal_alignment {
+            Horizontal::Left => "start",
+            Horizontal::Center => "middle",
+            Horizontal::Right => "end",
+        };
+        let baseline = match text.vertical_alignment {
+            Vertical::Top => "hanging",
+            Vertical::Center => "central",
+            Vertical::Bottom => "text-after-edge",
+        };
+        let weight = if matches!(
+            text.font.weight,
+            Weight::Semibold | Weight::Bold | Weight::ExtraBold | Weight::Black
+        ) {
+            "bold"
+        } else {
+            "normal"
+        };
+        let _ = writeln!(
+            self.body,
+            "<text x=\"{}\" y=\"{}\" font-family=\"sans-serif\" font-size=\"{}\" font-weight=\"{weight}\" fill=\"{color}\" fill-opacity=\"{opacity}\" text-anchor=\"{anchor}\" dominant-baseline=\"{baseline}\">{}</text>",
+            text.position.x,
+            text.position.y,
+            text.size.0,
+            Self::escape(&text.content),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn svg_output_parses_with_everything_painted() {
+        let mut svg = SvgPainter::new(Size::new(100., 50.), Color::BLACK);
+        svg.stroke(
+            &Path::line(Point::ORIGIN, Point::new(100., 50.)),
+            Stroke::default().with_width(2.),
+        );
+        svg.fill(&Path::circle(Point::new(50., 25.), 5.), Color::WHITE);
+        svg.fill_text(Text {
+            content: "a < b & c".to_string(),
+            position: Point::new(10., 10.),
+            ..Text::default()
+        });
+        let svg = svg.finish();
+
+        let tree = resvg::usvg::Tree::from_str(&svg, &resvg::usvg::Options::default()).unwrap();
+        assert_eq!(tree.size().width(), 100.);
+        assert!(svg.contains(">a &lt; b &amp; c</text>"));
+        assert!(svg.contains("stroke-width=\"2\""));
+        assert_eq!(svg.matches("<path").count(), 3);
+    }
+}
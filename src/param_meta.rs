@@ -0,0 +1,112 @@
+use crate::mode::ALL_MODES;
+use maccel_core::{AccelMode, Param};
+use std::ops::RangeInclusive;
+
+/// Values the driver's signed 32.32 fixed-point params stay below.
+const FIXED_POINT_LIMIT: f64 = (1u64 << 31) as f64;
+const LINEAR: &[AccelMode] = &[AccelMode::Linear];
+const NATURAL: &[AccelMode] = &[AccelMode::Natural];
+const SYNCHRONOUS: &[AccelMode] = &[AccelMode::Synchronous];
+
+/// What the GUI knows about a param, in the units the driver stores.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ParamMeta {
+    pub min: f64,
+    pub max: f64,
+    pub default: f64,
+    pub step: f64,
+    /// Unit shown next to the value; empty for plain ratios.
+    pub unit: &'static str,
+    pub description: &'static str,
+    /// Modes whose curve depends on the param.
+    pub modes: &'static [AccelMode],
+}
+
+impl ParamMeta {
+    pub const fn of(param: Param) -> Self {
+        #[rustfmt::skip]
+        let (min, max, default, step, unit, modes, description) = match param {
+            Param::SensMult => (0.05, 5., 1., 0.05, "×", ALL_MODES,
+                "Scales the whole curve; the sensitivity at zero speed."),
+            Param::YxRatio => (0.1, 5., 1., 0.05, "", ALL_MODES,
+                "Vertical sensitivity relative to horizontal."),
+            Param::InputDpi => (100., 32000., 1000., 50., "dpi", ALL_MODES,
+                "Your mouse's DPI, used to keep the curve the same across DPI changes."),
+            Param::Accel => (0., 1., 0., 0.005, "", LINEAR,
+                "Slope of the curve past the offset: how fast sensitivity grows with speed."),
+            Param::OffsetLinear => (0., 50., 0., 0.5, "counts/ms", LINEAR,
+                "Speed below which there is no acceleration; the flat start of the curve."),
+            Param::OutputCap => (0., 10., 0., 0.1, "×", LINEAR,
+                "Highest sensitivity the curve may reach; 0 for no cap."),
+            Param::DecayRate => (0., 1., 0., 0.01, "", NATURAL,
+                "How quickly the curve approaches its limit."),
+            Param::OffsetNatural => (0., 50., 0., 0.5, "counts/ms", NATURAL,
+                "Speed below which there is no acceleration; the flat start of the curve."),
+            Param::Limit => (0., 10., 0., 0.1, "×", NATURAL,
+                "Sensitivity the curve levels off at for fast movements."),
+            Param::Gamma => (0., 5., 0., 0.05, "", SYNCHRONOUS,
+                "How sharply the curve transitions around the sync speed."),
+            Param::Smooth => (0., 1., 0., 0.05, "", SYNCHRONOUS,
+                "Rounds off the transition around the sync speed."),
+            Param::Motivity => (0., 5., 0., 0.05, "×", SYNCHRONOUS,
+                "Ratio between the fastest and slowest sensitivity."),
+            Param::SyncSpeed => (0., 50., 0., 0.5, "counts/ms", SYNCHRONOUS,
+                "Speed at which the curve is at its midpoint."),
+        };
+        ParamMeta {
+            min,
+            max,
+            default,
+            step,
+            unit,
+            description,
+            modes,
+        }
+    }
+    pub fn range(self) -> RangeInclusive<f64> {
+        self.min..=self.max
+    }
+    pub fn used_in(self, mode: AccelMode) -> bool {
+        self.modes.contains(&mode)
+    }
+
+    /// Checks that a value entered for `param` is one the driver can take.
+    ///
+    /// The slider range is only a guide; see [`Self::warn_outside_range`].
+    pub fn validate(param: Param, value: f64) -> anyhow::Result<f64> {
+        let meta = Self::of(param);
+        let name = param.display_name();
+        anyhow::ensure!(value.is_finite(), "{name} must be a number");
+        if meta.min > 0. {
+            anyhow::ensure!(value > 0., "{name} must be greater than 0");
+        } else {
+            anyhow::ensure!(value >= 0., "{name} can't be negative");
+        }
+        anyhow::ensure!(
+            value < FIXED_POINT_LIMIT,
+            "{name} must be less than {FIXED_POINT_LIMIT}"
+        );
+        Ok(value)
+    }
+    /// A note for values past the slider's range, which are allowed but unusual.
+    pub fn warn_outside_range(param: Param, value: f64) -> Option<String> {
+        let meta = Self::of(param);
+        (!meta.range().contains(&value)).then(|| {
+            format!(
+                "{} is usually between {} and {}",
+                param.display_name(),
+                meta.min,
+                meta.max
+            )
+        })
+    }
+
+    /// Moves `value` by `steps` increments, snapped to a tenth of a step.
+    ///
+    /// Values typed in outside the range are never pushed further out.
+    pub fn nudge(self, value: f64, steps: f64) -> f64 {
+        let fine = self.step / 10.;
+        let v = ((value + steps * self.step) / fine).round() * fine;
+        v.clamp(self.min.min(value), self.max.max(value))
+    }
+}
@@ -0,0 +1,204 @@
+use crate::{SpeedUnit, Trace};
+use iced::alignment::{Horizontal, Vertical};
+use iced::mouse::Cursor;
+use iced::widget::canvas::path::Builder;
+use iced::widget::canvas::{Frame, Geometry, LineCap, LineJoin, Program, Stroke, Style, Text};
+use iced::{Pixels, Point, Rectangle, Renderer, Theme, Vector};
+use maccel_core::{ContextRef, persist::ParamStore, sensitivity};
+use std::f32::consts::TAU;
+use std::rc::Rc;
+
+/// One mouse report: motion in counts over `dt` milliseconds.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MouseDelta {
+    pub dx: f32,
+    pub dy: f32,
+    pub dt: f32,
+}
+
+impl MouseDelta {
+    const POLL_INTERVAL: f32 = 8.; // ms
+    const TURN_RATE: f32 = TAU / 3000.; // rad/ms
+
+    /// A slow lemniscate with a few fast flicks thrown in.
+    pub fn synthetic() -> Vec<Self> {
+        let dt = Self::POLL_INTERVAL;
+        (0..400)
+            .map(|i| {
+                let t = i as f32 / 400. * TAU;
+                let flick = if (i / 50) % 4 == 3 { 6. } else { 1. };
+                let (dx, dy) = (t.cos() * 12., (2. * t).cos() * 12.);
+                MouseDelta {
+                    dx: dx * flick,
+                    dy: dy * flick,
+                    dt,
+                }
+            })
+            .collect()
+    }
+    /// Recorded speeds laid along a steadily turning heading, since traces
+    /// only hold speeds; the direction is made up.
+    pub fn from_trace(trace: &Trace) -> Vec<Self> {
+        let mut heading = 0f32;
+        trace
+            .samples()
+            .windows(2)
+            .map(|w| {
+                let dt = (w[1].0 - w[0].0).as_secs_f32() * 1000.;
+                let dist = w[0].1 * dt;
+                heading += Self::TURN_RATE * dt;
+                MouseDelta {
+                    dx: dist * heading.cos(),
+                    dy: dist * heading.sin(),
+                    dt,
+                }
+            })
+            .collect()
+    }
+}
+
+/// The path a sequence of [`MouseDelta`]s traces with and without acceleration.
+#[derive(Debug)]
+pub struct PathPreview<PS: ParamStore> {
+    context: ContextRef<PS>,
+    deltas: Rc<[MouseDelta]>,
+    caption: Option<&'static str>,
+}
+
+impl<PS: ParamStore> PathPreview<PS> {
+    const MARGIN: f32 = 20.;
+
+    pub fn new(context: ContextRef<PS>, deltas: Rc<[MouseDelta]>) -> Self {
+        PathPreview {
+            context,
+            deltas,
+            caption: None,
+        }
+    }
+    /// A note drawn under the legend, e.g. on where the motion came from.
+    pub fn with_caption(mut self, caption: &'static str) -> Self {
+        self.caption = Some(caption);
+        self
+    }
+    /// Cumulative raw and accelerated positions, both starting at the origin.
+    pub fn paths(&self) -> (Vec<Point>, Vec<Point>) {
+        let context = self.context.get();
+        let params = context.params_snapshot();
+        let dpi = f64::from(params.input_dpi);
+
+        let mut raw = vec![Point::ORIGIN];
+        let mut accel = vec![Point::ORIGIN];
+        for d in self.deltas.iter().filter(|d| d.dt > 0.) {
+            let speed = SpeedUnit::normalise((d.dx.hypot(d.dy) / d.dt) as f64, dpi);
+            let (x_sens, y_sens) = sensitivity(speed, context.current_mode, &params);
+            let (r, a) = (*raw.last().unwrap(), *accel.last().unwrap());
+            raw.push(r + Vector::new(d.dx, d.dy));
+            accel.push(a + Vector::new(d.dx * x_sens as f32, d.dy * y_sens as f32));
+        }
+        (raw, accel)
+    }
+}
+
+impl<M, PS: ParamStore> Program<M> for PathPreview<PS> {
+    type State = ();
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        let (raw, accel) = self.paths();
+        let palette = theme.extended_palette();
+        let (raw_color, accel_color) =
+            (palette.background.strong.color, palette.primary.base.color);
+
+        let (min, max) =
+            raw.iter()
+                .chain(&accel)
+                .fold((Point::ORIGIN, Point::ORIGIN), |(min, max), p| {
+                    (
+                        Point::new(min.x.min(p.x), min.y.min(p.y)),
+                        Point::new(max.x.max(p.x), max.y.max(p.y)),
+                    )
+                });
+        let extent = (max - min).x.max((max - min).y).max(1.);
+        let scale = (bounds.width.min(bounds.height) - 2. * Self::MARGIN) / extent;
+        let offset = Vector::new(
+            (bounds.width - (max.x - min.x) * scale) / 2.,
+            (bounds.height - (max.y - min.y) * scale) / 2.,
+        );
+        let to_screen =
+            |p: &Point| Point::new((p.x - min.x) * scale, (p.y - min.y) * scale) + offset;
+
+        for (points, color, width) in [(&raw, raw_color, 1.), (&accel, accel_color, 2.)] {
+            let mut bld = Builder::new();
+            bld.move_to(to_screen(&points[0]));
+            for p in &points[1..] {
+                bld.line_to(to_screen(p));
+            }
+            frame.stroke(
+                &bld.build(),
+                Stroke {
+                    style: Style::Solid(color),
+                    width,
+                    line_cap: LineCap::Round,
+                    line_join: LineJoin::Round,
+                    ..Stroke::default()
+                },
+            );
+        }
+
+        let caption = self
+            .caption
+            .map(|c| (c, palette.background.base.text.scale_alpha(0.7)));
+        for (i, (label, color)) in [("raw input", raw_color), ("with acceleration", accel_color)]
+            .into_iter()
+            .chain(caption)
+            .enumerate()
+        {
+            frame.fill_text(Text {
+                content: label.to_string(),
+                position: Point::new(Self::MARGIN, Self::MARGIN + i as f32 * 14.),
+                color,
+                size: Pixels(12.),
+                horizontal_alignment: Horizontal::Left,
+                vertical_alignment: Vertical::Top,
+                ..Text::default()
+            });
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RuntimeStore;
+    use maccel_core::{ALL_PARAMS, Param, TuiContext};
+
+    fn paths(store: RuntimeStore) -> (Vec<Point>, Vec<Point>) {
+        let context = ContextRef::new(TuiContext::new(store, ALL_PARAMS));
+        PathPreview::new(context, MouseDelta::synthetic().into()).paths()
+    }
+
+    #[test]
+    fn identity_params_leave_the_path_alone() {
+        let (raw, accel) = paths(RuntimeStore::new());
+        assert_eq!(raw.len(), MouseDelta::synthetic().len() + 1);
+        assert_eq!(raw, accel);
+    }
+
+    #[test]
+    fn yx_ratio_only_scales_vertical_motion() {
+        let (raw, accel) = paths(RuntimeStore::new().with_value(Param::YxRatio, 2.));
+        for (r, a) in raw.iter().zip(&accel) {
+            assert!((a.x - r.x).abs() < 1e-3, "{r:?} {a:?}");
+            assert!((a.y - 2. * r.y).abs() < 1e-3, "{r:?} {a:?}");
+        }
+        assert!(raw.iter().any(|p| p.y.abs() > 1.));
+    }
+}
@@ -0,0 +1,67 @@
+use std::env::var_os;
+use std::path::{Path, PathBuf};
+
+const APP_DIR: &str = "maccel-gui";
+
+/// `$XDG_CONFIG_HOME/maccel-gui`, falling back to `~/.config/maccel-gui`.
+pub fn config_dir() -> PathBuf {
+    xdg_dir("XDG_CONFIG_HOME", ".config")
+}
+
+/// `$XDG_DATA_HOME/maccel-gui`, falling back to `~/.local/share/maccel-gui`.
+pub fn data_dir() -> PathBuf {
+    xdg_dir("XDG_DATA_HOME", ".local/share")
+}
+
+fn xdg_dir(env: &str, home_fallback: &str) -> PathBuf {
+    var_os(env)
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| var_os("HOME").map(|home| PathBuf::from(home).join(home_fallback)))
+        .unwrap_or_default()
+        .join(APP_DIR)
+}
+
+/// Every file the app reads or writes on its own, so tests can keep them in
+/// a scratch directory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AppPaths {
+    pub preferences: PathBuf,
+    pub keymap: PathBuf,
+    pub graph_theme: PathBuf,
+    pub histogram: PathBuf,
+    pub trace: PathBuf,
+    pub exports: PathBuf,
+    pub profiles: PathBuf,
+}
+
+impl Default for AppPaths {
+    fn default() -> Self {
+        AppPaths {
+            preferences: crate::Preferences::default_path(),
+            keymap: crate::Keymap::default_path(),
+            graph_theme: crate::GraphThemeFile::default_path(),
+            histogram: crate::SpeedHistogram::default_path(),
+            trace: crate::Trace::default_path(),
+            exports: crate::export::default_dir(),
+            profiles: crate::Profile::default_dir(),
+        }
+    }
+}
+
+impl AppPaths {
+    /// All files directly inside `dir`, named as they are by default.
+    pub fn under(dir: &Path) -> Self {
+        let in_dir = |path: PathBuf| dir.join(path.file_name().unwrap_or_default());
+        let default = AppPaths::default();
+        AppPaths {
+            preferences: in_dir(default.preferences),
+            keymap: in_dir(default.keymap),
+            graph_theme: in_dir(default.graph_theme),
+            histogram: in_dir(default.histogram),
+            trace: in_dir(default.trace),
+            exports: in_dir(default.exports),
+            profiles: in_dir(default.profiles),
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use crate::{Overlays, SpeedUnit};
+use anyhow::Context;
+use iced::{Point, Size, Theme};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// GUI settings kept between sessions; driver parameters are not included.
+///
+/// Neither is the acceleration mode: like the parameters it lives in the
+/// driver, which keeps it between sessions on its own, so restoring a saved
+/// one at startup would undo changes made with other tools meanwhile.
+///
+/// Every field falls back to its default, so files from older versions
+/// still load and unknown keys from newer ones are ignored.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preferences {
+    pub version: u32,
+    pub window: WindowPrefs,
+    /// Name of an iced built-in theme.
+    pub theme: String,
+    pub zoom: f32,
+    pub overlays: Overlays,
+    pub unit: SpeedUnit,
+    /// Whether the cursor path preview was showing instead of the curve.
+    pub path_preview: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowPrefs {
+    pub width: f32,
+    pub height: f32,
+    pub x: Option<f32>,
+    pub y: Option<f32>,
+}
+
+impl Default for WindowPrefs {
+    fn default() -> Self {
+        WindowPrefs {
+            width: 1024.,
+            height: 768.,
+            x: None,
+            y: None,
+        }
+    }
+}
+
+impl WindowPrefs {
+    pub fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+    pub fn position(&self) -> Option<Point> {
+        Some(Point::new(self.x?, self.y?))
+    }
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Preferences {
+            version: Self::VERSION,
+            window: WindowPrefs::default(),
+            theme: Theme::CatppuccinMocha.to_string(),
+            zoom: 1.,
+            overlays: Overlays::default(),
+            unit: SpeedUnit::default(),
+            path_preview: false,
+        }
+    }
+}
+
+impl Preferences {
+    pub const VERSION: u32 = 1;
+
+    pub fn default_path() -> PathBuf {
+        crate::paths::config_dir().join("preferences.toml")
+    }
+
+    /// The saved theme, if it still names a built-in one.
+    pub fn theme(&self) -> Option<Theme> {
+        Theme::ALL
+            .iter()
+            .find(|t| t.to_string() == self.theme)
+            .cloned()
+    }
+
+    /// Loads the preferences at `path`, falling back to defaults.
+    ///
+    /// A file that can't be parsed is moved aside to `<path>.bak` so the
+    /// next save does not silently destroy it, and a warning saying so is
+    /// returned for the user.
+    pub fn load_or_default(path: &Path) -> (Self, Option<anyhow::Error>) {
+        if !path.exists() {
+            return (Preferences::default(), None);
+        }
+        match Self::load(path) {
+            Ok(prefs) => (prefs, None),
+            Err(e) => {
+                let backup = path.with_extension("toml.bak");
+                let warning = match fs::rename(path, &backup) {
+                    Ok(()) => format!(
+                        "settings were reset, the old ones are in {}",
+                        backup.display()
+                    ),
+                    Err(moving) => format!(
+                        "settings were reset, and moving {} aside failed ({moving})",
+                        path.display()
+                    ),
+                };
+                (Preferences::default(), Some(e.context(warning)))
+            }
+        }
+    }
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("failed reading {}", path.display()))?;
+        let mut prefs: Preferences = toml::from_str(&content)
+            .with_context(|| format!("failed parsing {}", path.display()))?;
+        prefs.version = Self::VERSION;
+        Ok(prefs)
+    }
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let content = toml::to_string_pretty(self).context("failed serializing preferences")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("failed creating {}", dir.display()))?;
+        }
+        fs::write(path, content).with_context(|| format!("failed writing {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("maccel-gui-prefs-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join(name)
+    }
+
+    #[test]
+    fn round_trips() {
+        let path = temp_path("round_trip.toml");
+        let mut prefs = Preferences {
+            theme: Theme::Nord.to_string(),
+            zoom: 2.,
+            unit: SpeedUnit::CmPerSec,
+            path_preview: true,
+            ..Preferences::default()
+        };
+        prefs.window.x = Some(10.);
+        prefs.window.y = Some(20.);
+        prefs.overlays.minor_grid = true;
+        prefs.save(&path).unwrap();
+        assert_eq!(Preferences::load(&path).unwrap(), prefs);
+        assert_eq!(prefs.theme(), Some(Theme::Nord));
+    }
+
+    #[test]
+    fn older_files_fill_in_defaults() {
+        let path = temp_path("older.toml");
+        fs::write(&path, "zoom = 0.5\n[window]\nwidth = 640.0\n").unwrap();
+        let (prefs, warning) = Preferences::load_or_default(&path);
+        assert!(warning.is_none());
+        assert_eq!(prefs.zoom, 0.5);
+        assert_eq!(prefs.window.width, 640.);
+        assert_eq!(prefs.window.height, WindowPrefs::default().height);
+        assert_eq!(prefs.overlays, Overlays::default());
+        assert_eq!(prefs.version, Preferences::VERSION);
+    }
+
+    #[test]
+    fn corrupt_files_are_moved_aside() {
+        let path = temp_path("corrupt.toml");
+        fs::write(&path, "zoom = [[[").unwrap();
+        let (prefs, warning) = Preferences::load_or_default(&path);
+        assert_eq!(prefs, Preferences::default());
+        let warning = format!("{:#}", warning.unwrap());
+        assert!(warning.starts_with("settings were reset"), "{warning}");
+        assert!(warning.contains("failed parsing"), "{warning}");
+        assert!(!path.exists());
+        assert!(path.with_extension("toml.bak").exists());
+    }
+
+    #[test]
+    fn unknown_theme_is_ignored() {
+        let prefs = Preferences {
+            theme: "No Such Theme".to_string(),
+            ..Preferences::default()
+        };
+        assert_eq!(prefs.theme(), None);
+    }
+}
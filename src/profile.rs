@@ -0,0 +1,139 @@
+use crate::ParamMeta;
+use crate::mode::{mode_name, parse_mode};
+use anyhow::Context;
+use maccel_core::{ALL_PARAMS, AccelMode, Param};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A named set of parameter values and the mode they are for, kept as
+/// `<name>.toml` in the profiles directory.
+///
+/// Params left out of the file are not touched when it is loaded.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub mode: String,
+    pub params: BTreeMap<String, f64>,
+}
+
+impl Profile {
+    pub fn default_dir() -> PathBuf {
+        crate::paths::config_dir().join("profiles")
+    }
+
+    /// `values` are indexed by `param as usize`.
+    pub fn new(mode: AccelMode, values: &[f64]) -> Self {
+        Profile {
+            mode: mode_name(mode).to_string(),
+            params: ALL_PARAMS
+                .iter()
+                .map(|&p| (p.name().to_string(), values[p as usize]))
+                .collect(),
+        }
+    }
+
+    /// The mode and values to apply, checked like values typed into a field.
+    pub fn resolve(&self) -> anyhow::Result<(AccelMode, Vec<(Param, f64)>)> {
+        let mode = parse_mode(&self.mode)
+            .with_context(|| format!("unknown acceleration mode {:?}", self.mode))?;
+        let values = self
+            .params
+            .iter()
+            .map(|(name, &value)| {
+                let param = ALL_PARAMS
+                    .iter()
+                    .copied()
+                    .find(|p| p.name() == name)
+                    .with_context(|| format!("unknown param {name:?}"))?;
+                Ok((param, ParamMeta::validate(param, value)?))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok((mode, values))
+    }
+
+    /// Names of the profiles saved in `dir`, sorted.
+    pub fn list(dir: &Path) -> anyhow::Result<Vec<String>> {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e).with_context(|| format!("failed reading {}", dir.display())),
+        };
+        let mut names = vec![];
+        for entry in entries {
+            let path = entry
+                .with_context(|| format!("failed reading {}", dir.display()))?
+                .path();
+            let is_toml = path.extension().is_some_and(|ext| ext == "toml");
+            if let Some(name) = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .filter(|_| is_toml)
+            {
+                names.push(name.to_string());
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+    pub fn load(dir: &Path, name: &str) -> anyhow::Result<Self> {
+        let path = dir.join(format!("{name}.toml"));
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("failed reading {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("failed parsing {}", path.display()))
+    }
+    pub fn save(&self, dir: &Path, name: &str) -> anyhow::Result<()> {
+        let path = dir.join(format!("{name}.toml"));
+        let content = toml::to_string_pretty(self).context("failed serializing profile")?;
+        fs::create_dir_all(dir).with_context(|| format!("failed creating {}", dir.display()))?;
+        fs::write(&path, content).with_context(|| format!("failed writing {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("maccel-gui-profiles-{}", std::process::id()))
+            .join(name);
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn round_trips_and_lists_by_name() {
+        let dir = temp_dir("round_trip");
+        assert!(Profile::list(&dir).unwrap().is_empty());
+        let mut values = [1.; ALL_PARAMS.len()];
+        values[Param::Accel as usize] = 0.25;
+        let profile = Profile::new(AccelMode::Natural, &values);
+        profile.save(&dir, "work").unwrap();
+        profile.save(&dir, "games").unwrap();
+        fs::write(dir.join("notes.txt"), "").unwrap();
+
+        assert_eq!(Profile::list(&dir).unwrap(), ["games", "work"]);
+        let loaded = Profile::load(&dir, "work").unwrap();
+        assert_eq!(loaded, profile);
+        let (mode, resolved) = loaded.resolve().unwrap();
+        assert_eq!(mode, AccelMode::Natural);
+        assert!(resolved.contains(&(Param::Accel, 0.25)));
+    }
+
+    #[test]
+    fn bad_profiles_are_rejected() {
+        let profile = |mode: &str, name: &str, value: f64| Profile {
+            mode: mode.to_string(),
+            params: [(name.to_string(), value)].into(),
+        };
+        let err = |p: Profile| format!("{:#}", p.resolve().unwrap_err());
+        let accel = Param::Accel.name();
+        assert!(err(profile("sideways", accel, 1.)).contains("unknown acceleration mode"));
+        assert!(err(profile("linear", "NOPE", 1.)).contains("unknown param"));
+        assert!(err(profile("linear", accel, -1.)).contains("negative"));
+        assert!(profile("linear", accel, 1.).resolve().is_ok());
+    }
+}
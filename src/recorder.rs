@@ -0,0 +1,155 @@
+use anyhow::Context;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Distribution of input speeds collected over a recording session.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SpeedHistogram {
+    counts: Vec<u64>,
+    total: u64,
+}
+
+impl SpeedHistogram {
+    pub const BIN_WIDTH: f32 = 0.25; // counts/ms
+    pub const PERCENTILES: [f32; 3] = [0.5, 0.9, 0.99];
+    /// Anything faster lands in the last bin, like a driver spike would.
+    pub const MAX_BINS: usize = 4000;
+    const HEADER: &str = "# maccel-gui input speed histogram: <bin start (counts/ms)> <samples>";
+
+    pub fn default_path() -> PathBuf {
+        crate::paths::data_dir().join("speed_histogram.txt")
+    }
+
+    /// Only samples of actual movement are recorded.
+    pub fn record(&mut self, speed: f32) {
+        if !speed.is_finite() || speed <= 0. {
+            return;
+        }
+        let bin = ((speed / Self::BIN_WIDTH) as usize).min(Self::MAX_BINS - 1);
+        self.add(bin, 1);
+    }
+    fn add(&mut self, bin: usize, n: u64) {
+        if bin >= self.counts.len() {
+            self.counts.resize(bin + 1, 0);
+        }
+        self.counts[bin] = self.counts[bin].saturating_add(n);
+        self.total = self.total.saturating_add(n);
+    }
+    pub fn is_empty(&self) -> bool {
+        self.total == 0
+    }
+    pub fn clear(&mut self) {
+        *self = SpeedHistogram::default();
+    }
+
+    /// Upper edge of the bin containing the `p`-th quantile, `p` in `0..=1`.
+    pub fn percentile(&self, p: f32) -> Option<f32> {
+        if self.is_empty() {
+            return None;
+        }
+        let target = (p.clamp(0., 1.) * self.total as f32).ceil().max(1.) as u64;
+        let mut seen = 0;
+        self.counts.iter().enumerate().find_map(|(bin, &n)| {
+            seen += n;
+            (seen >= target).then_some((bin + 1) as f32 * Self::BIN_WIDTH)
+        })
+    }
+    pub fn percentiles(&self) -> Option<[f32; 3]> {
+        let [p50, p90, p99] = Self::PERCENTILES.map(|p| self.percentile(p));
+        Some([p50?, p90?, p99?])
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let mut out = String::new();
+        writeln!(out, "{}", Self::HEADER)?;
+        for (bin, &n) in self.counts.iter().enumerate().filter(|(_, n)| **n > 0) {
+            writeln!(out, "{} {}", bin as f32 * Self::BIN_WIDTH, n)?;
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("failed creating {}", dir.display()))?;
+        }
+        fs::write(path, out).with_context(|| format!("failed writing {}", path.display()))
+    }
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("failed reading {}", path.display()))?;
+        let mut hist = SpeedHistogram::default();
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let parse = || -> Option<(usize, u64)> {
+                let (speed, n) = line.split_once(char::is_whitespace)?;
+                let bin = (speed.parse::<f32>().ok()? / Self::BIN_WIDTH).round();
+                let bin = (0. ..Self::MAX_BINS as f32)
+                    .contains(&bin)
+                    .then_some(bin as usize)?;
+                Some((bin, n.trim().parse().ok()?))
+            };
+            let (bin, n) = parse()
+                .with_context(|| format!("{}:{}: malformed entry", path.display(), i + 1))?;
+            hist.add(bin, n);
+        }
+        Ok(hist)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("maccel-gui-hist-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join(name)
+    }
+
+    #[test]
+    fn percentiles_are_upper_bin_edges() {
+        let mut hist = SpeedHistogram::default();
+        assert_eq!(hist.percentiles(), None);
+        for (speed, n) in [(1., 90), (5., 9), (20., 1)] {
+            (0..n).for_each(|_| hist.record(speed));
+        }
+        hist.record(0.);
+        assert_eq!(hist.percentiles(), Some([1.25, 1.25, 5.25]));
+        assert_eq!(hist.percentile(1.), Some(20.25));
+        assert_eq!(hist.percentile(0.), Some(1.25));
+
+        let path = temp_path("round_trip.txt");
+        hist.save(&path).unwrap();
+        assert_eq!(SpeedHistogram::load(&path).unwrap(), hist);
+    }
+
+    #[test]
+    fn spikes_land_in_the_last_bin() {
+        let mut hist = SpeedHistogram::default();
+        for speed in [1e12, f32::MAX, 2.] {
+            hist.record(speed);
+        }
+        hist.record(f32::INFINITY);
+        hist.record(f32::NAN);
+        assert_eq!(hist.counts.len(), SpeedHistogram::MAX_BINS);
+        assert_eq!(hist.total, 3);
+        assert_eq!(
+            hist.percentile(1.),
+            Some(SpeedHistogram::MAX_BINS as f32 * SpeedHistogram::BIN_WIDTH)
+        );
+    }
+
+    #[test]
+    fn absurd_saved_speeds_are_rejected() {
+        let path = temp_path("absurd.txt");
+        for entry in ["1e12 1", "inf 1", "NaN 1", "-1 1"] {
+            fs::write(&path, format!("0.25 3\n{entry}\n")).unwrap();
+            let err = SpeedHistogram::load(&path).unwrap_err();
+            assert!(
+                format!("{err:#}").contains(":2: malformed entry"),
+                "{entry}"
+            );
+        }
+    }
+}
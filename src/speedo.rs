@@ -0,0 +1,133 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Short-term history of the input speed, sampled once per frame.
+#[derive(Clone, Debug)]
+pub struct Speedometer {
+    current: f32,
+    peak: f32,
+    peak_at: Instant,
+    last: Instant,
+    trail: VecDeque<(f32, Instant)>,
+    histogram: [f32; Speedometer::HISTOGRAM_BINS],
+}
+
+impl Speedometer {
+    /// One bin per count/ms, anything faster lands in the last bin.
+    pub const HISTOGRAM_BINS: usize = 80;
+    pub const TRAIL_AGE: Duration = Duration::from_millis(750);
+    const PEAK_HOLD: Duration = Duration::from_secs(1);
+    const PEAK_DECAY: f32 = 40.; // counts/ms per second
+    const HISTOGRAM_HALF_LIFE: f32 = 10.; // seconds
+
+    pub fn new() -> Self {
+        let now = Instant::now();
+        Speedometer {
+            current: 0.,
+            peak: 0.,
+            peak_at: now,
+            last: now,
+            trail: VecDeque::new(),
+            histogram: [0.; Speedometer::HISTOGRAM_BINS],
+        }
+    }
+
+    pub fn record(&mut self, speed: f32, now: Instant) {
+        let dt = now.saturating_duration_since(self.last).as_secs_f32();
+        self.last = now;
+        self.current = speed;
+
+        if speed >= self.peak {
+            self.peak = speed;
+            self.peak_at = now;
+        } else if now.saturating_duration_since(self.peak_at) > Self::PEAK_HOLD {
+            self.peak = (self.peak - Self::PEAK_DECAY * dt).max(speed);
+        }
+
+        let decay = 0.5f32.powf(dt / Self::HISTOGRAM_HALF_LIFE);
+        self.histogram.iter_mut().for_each(|n| *n *= decay);
+        while self
+            .trail
+            .front()
+            .is_some_and(|&(_, t)| now.saturating_duration_since(t) > Self::TRAIL_AGE)
+        {
+            self.trail.pop_front();
+        }
+        if speed > 0. {
+            self.histogram[(speed as usize).min(Self::HISTOGRAM_BINS - 1)] += 1.;
+            self.trail.push_back((speed, now));
+        }
+    }
+
+    pub fn current(&self) -> f32 {
+        self.current
+    }
+    pub fn peak(&self) -> f32 {
+        self.peak
+    }
+    /// Recent speeds paired with their remaining opacity, oldest first.
+    pub fn trail(&self) -> impl Iterator<Item = (f32, f32)> + '_ {
+        self.trail.iter().map(|&(speed, t)| {
+            let age = self.last.saturating_duration_since(t).as_secs_f32();
+            (speed, 1. - age / Self::TRAIL_AGE.as_secs_f32())
+        })
+    }
+    /// Decayed sample counts, normalised so the fullest bin is 1.
+    pub fn histogram(&self) -> impl Iterator<Item = f32> + '_ {
+        let max = self.histogram.iter().copied().fold(0., f32::max);
+        self.histogram
+            .iter()
+            .map(move |&n| if max > 0. { n / max } else { 0. })
+    }
+}
+
+impl Default for Speedometer {
+    fn default() -> Self {
+        Speedometer::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn peak_is_held_then_decays() {
+        let t0 = Instant::now();
+        let mut speedo = Speedometer::new();
+        speedo.record(50., t0);
+        speedo.record(2., t0 + ms(500));
+        assert_eq!(speedo.peak(), 50.);
+        assert_eq!(speedo.current(), 2.);
+
+        // 40 counts/ms per second once the hold is over
+        speedo.record(2., t0 + ms(1100));
+        assert!((speedo.peak() - 26.).abs() < 1e-3);
+        speedo.record(2., t0 + ms(3000));
+        assert_eq!(speedo.peak(), 2.);
+        speedo.record(30., t0 + ms(3100));
+        assert_eq!(speedo.peak(), 30.);
+    }
+
+    #[test]
+    fn old_trail_points_and_fast_speeds_are_kept_in_bounds() {
+        let t0 = Instant::now();
+        let mut speedo = Speedometer::new();
+        speedo.record(5., t0);
+        speedo.record(500., t0 + ms(100));
+        speedo.record(0., t0 + ms(800));
+        let trail: Vec<_> = speedo.trail().collect();
+        assert_eq!(trail.len(), 1);
+        assert_eq!(trail[0].0, 500.);
+        assert!(trail[0].1 > 0. && trail[0].1 < 0.1);
+
+        let histogram: Vec<_> = speedo.histogram().collect();
+        assert_eq!(histogram.len(), Speedometer::HISTOGRAM_BINS);
+        assert_eq!(histogram[Speedometer::HISTOGRAM_BINS - 1], 1.);
+        assert!(histogram[5] > 0.9 && histogram[5] < 1.);
+    }
+}
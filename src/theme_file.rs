@@ -0,0 +1,188 @@
+use anyhow::Context;
+use iced::Color;
+use serde::{Deserialize, Deserializer};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// User overrides for [`GraphTheme`](crate::GraphTheme), read from TOML.
+///
+/// Every field is optional; anything left out keeps the built-in style.
+/// Colours are written as `"#rrggbb"` or `"#rrggbbaa"`.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct GraphThemeFile {
+    pub h_plot: StrokeSpec,
+    pub v_plot: StrokeSpec,
+    pub x_axis: StrokeSpec,
+    pub y_axis: StrokeSpec,
+    pub h_speedo: FillSpec,
+    pub v_speedo: FillSpec,
+    pub peak: StrokeSpec,
+    pub trail: FillSpec,
+    pub histogram: FillSpec,
+    pub percentiles: StrokeSpec,
+    pub grid: StrokeSpec,
+    pub minor_grid: StrokeSpec,
+    pub reference: StrokeSpec,
+    pub ticks: StrokeSpec,
+    pub labels: LabelSpec,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct StrokeSpec {
+    #[serde(deserialize_with = "hex_color")]
+    pub color: Option<Color>,
+    pub width: Option<f32>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct FillSpec {
+    #[serde(deserialize_with = "hex_color")]
+    pub color: Option<Color>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct LabelSpec {
+    #[serde(deserialize_with = "hex_color")]
+    pub color: Option<Color>,
+    pub size: Option<f32>,
+}
+
+fn hex_color<'de, D: Deserializer<'de>>(de: D) -> Result<Option<Color>, D::Error> {
+    let s = String::deserialize(de)?;
+    Color::parse(&s)
+        .map(Some)
+        .ok_or_else(|| serde::de::Error::custom(format!("invalid colour {s:?}")))
+}
+
+impl GraphThemeFile {
+    pub fn default_path() -> PathBuf {
+        crate::paths::config_dir().join("graph_theme.toml")
+    }
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("failed reading {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("failed parsing {}", path.display()))
+    }
+}
+
+/// Reloads a [`GraphThemeFile`] whenever its modification time changes.
+#[derive(Debug)]
+pub struct ThemeFileWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    checked: Option<Instant>,
+    current: GraphThemeFile,
+}
+
+impl ThemeFileWatcher {
+    const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+    /// Starts with the built-in style; the file is read on the first [`Self::poll`].
+    pub fn new(path: PathBuf) -> Self {
+        ThemeFileWatcher {
+            path,
+            modified: None,
+            checked: None,
+            current: GraphThemeFile::default(),
+        }
+    }
+    pub fn current(&self) -> &GraphThemeFile {
+        &self.current
+    }
+    /// Returns whether the overrides changed. A file that fails to load
+    /// leaves the previous overrides in place, and its error is returned
+    /// once, until the file changes again.
+    pub fn poll(&mut self, now: Instant) -> anyhow::Result<bool> {
+        if self
+            .checked
+            .is_some_and(|t| now.saturating_duration_since(t) < Self::POLL_INTERVAL)
+        {
+            return Ok(false);
+        }
+        self.checked = Some(now);
+
+        let modified = fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+        if modified == self.modified {
+            return Ok(false);
+        }
+        self.modified = modified;
+        let file = match modified {
+            Some(_) => GraphThemeFile::load(&self.path)?,
+            None => GraphThemeFile::default(),
+        };
+        let changed = self.current != file;
+        self.current = file;
+        Ok(changed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("maccel-gui-theme-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join(name)
+    }
+    /// Writes `content` with a distinct modification time, as coarse
+    /// filesystem timestamps could otherwise hide the change.
+    fn write(path: &Path, content: &str, age_secs: u64) {
+        fs::write(path, content).unwrap();
+        let mtime = SystemTime::now() - Duration::from_secs(age_secs);
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(mtime)
+            .unwrap();
+    }
+
+    #[test]
+    fn parses_hex_colours() {
+        let file: GraphThemeFile = toml::from_str(
+            "[h_plot]\ncolor = \"#ff8000\"\nwidth = 2.5\n[labels]\ncolor = \"#00000080\"\n",
+        )
+        .unwrap();
+        assert_eq!(file.h_plot.color, Some(Color::from_rgb8(255, 128, 0)));
+        assert_eq!(file.h_plot.width, Some(2.5));
+        assert_eq!(file.labels.color.map(|c| c.a), Some(128. / 255.));
+        assert_eq!(file.v_plot, StrokeSpec::default());
+
+        assert!(toml::from_str::<GraphThemeFile>("[grid]\ncolor = \"red\"").is_err());
+        assert!(toml::from_str::<GraphThemeFile>("[grid]\nwdth = 1.0").is_err());
+    }
+
+    #[test]
+    fn polling_reloads_changed_files() {
+        let path = temp_path("reload.toml");
+        let _ = fs::remove_file(&path);
+        let mut watcher = ThemeFileWatcher::new(path.clone());
+        let mut now = Instant::now();
+        let mut poll = |watcher: &mut ThemeFileWatcher| {
+            now += ThemeFileWatcher::POLL_INTERVAL;
+            watcher.poll(now)
+        };
+        assert!(!poll(&mut watcher).unwrap());
+
+        write(&path, "[grid]\nwidth = 3.0\n", 30);
+        assert!(poll(&mut watcher).unwrap());
+        assert_eq!(watcher.current().grid.width, Some(3.));
+        assert!(!poll(&mut watcher).unwrap());
+
+        write(&path, "[grid]\nwidth = [[[", 20);
+        let err = poll(&mut watcher).unwrap_err();
+        assert!(format!("{err:#}").contains("failed parsing"));
+        assert!(!poll(&mut watcher).unwrap());
+        assert_eq!(watcher.current().grid.width, Some(3.));
+
+        fs::remove_file(&path).unwrap();
+        assert!(poll(&mut watcher).unwrap());
+        assert_eq!(*watcher.current(), GraphThemeFile::default());
+    }
+}
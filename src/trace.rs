@@ -0,0 +1,285 @@
+use crate::mode::{mode_name, parse_mode};
+use anyhow::Context;
+use maccel_core::{ALL_PARAMS, AccelMode, AllParamArgs, Param, sensitivity};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Timestamped input speed samples, as recorded from a live session, along
+/// with the curve that was active while recording.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Trace {
+    samples: Vec<(Duration, f32)>,
+    curve: Option<(AccelMode, [f64; ALL_PARAMS.len()])>,
+}
+
+/// Total motion produced by replaying a [`Trace`] through two curves.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ReplayReport {
+    pub input: f64,
+    pub old: (f64, f64),
+    pub new: (f64, f64),
+}
+
+impl Trace {
+    const HEADER: &str = "# maccel-gui input speed trace: <time (ms)> <speed (counts/ms)>";
+    const MODE_KEY: &str = "MODE";
+
+    pub fn default_path() -> PathBuf {
+        crate::paths::data_dir().join("trace.txt")
+    }
+
+    pub fn new(mode: AccelMode, params: [f64; ALL_PARAMS.len()]) -> Self {
+        Trace {
+            samples: Vec::new(),
+            curve: Some((mode, params)),
+        }
+    }
+    pub fn push(&mut self, t: Duration, speed: f32) {
+        if self.samples.last().is_none_or(|&(last, _)| last <= t) {
+            self.samples.push((t, speed));
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let mut out = String::new();
+        writeln!(out, "{}", Self::HEADER)?;
+        if let Some((mode, values)) = &self.curve {
+            writeln!(out, "#! {} {}", Self::MODE_KEY, mode_name(*mode))?;
+            for &param in ALL_PARAMS {
+                writeln!(out, "#! {} {}", param.name(), values[param as usize])?;
+            }
+        }
+        for (t, speed) in &self.samples {
+            writeln!(out, "{} {}", t.as_secs_f64() * 1000., speed)?;
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("failed creating {}", dir.display()))?;
+        }
+        fs::write(path, out).with_context(|| format!("failed writing {}", path.display()))
+    }
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("failed reading {}", path.display()))?;
+        let mut samples = Vec::new();
+        let mut mode = None;
+        let mut values = [None; ALL_PARAMS.len()];
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if let Some(meta) = line.strip_prefix("#!") {
+                let Some((key, value)) = meta.trim().split_once(char::is_whitespace) else {
+                    continue;
+                };
+                let value = value.trim();
+                if key == Self::MODE_KEY {
+                    mode = parse_mode(value);
+                } else if let Some(&param) = ALL_PARAMS.iter().find(|p| p.name() == key) {
+                    values[param as usize] = value.parse::<f64>().ok();
+                }
+                continue;
+            }
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let parse = || -> Option<(Duration, f32)> {
+                let (ms, speed) = line.split_once(char::is_whitespace)?;
+                let ms = ms.parse::<f64>().ok()?;
+                Some((
+                    Duration::try_from_secs_f64(ms / 1000.).ok()?,
+                    speed.trim().parse().ok()?,
+                ))
+            };
+            let sample = parse()
+                .with_context(|| format!("{}:{}: malformed sample", path.display(), i + 1))?;
+            samples.push(sample);
+        }
+        samples.sort_by_key(|&(t, _)| t);
+        // the curve is only usable if every param made it into the header
+        let values = values
+            .into_iter()
+            .collect::<Option<Vec<_>>>()
+            .and_then(|v| v.try_into().ok());
+        Ok(Trace {
+            samples,
+            curve: mode.zip(values),
+        })
+    }
+
+    pub fn samples(&self) -> &[(Duration, f32)] {
+        &self.samples
+    }
+    pub fn curve(&self) -> Option<(AccelMode, [f64; ALL_PARAMS.len()])> {
+        self.curve
+    }
+    pub fn duration(&self) -> Duration {
+        self.samples.last().map_or(Duration::ZERO, |&(t, _)| t)
+    }
+    /// Speed of the latest sample at or before `t`.
+    pub fn speed_at(&self, t: Duration) -> f32 {
+        match self.samples.partition_point(|&(ts, _)| ts <= t) {
+            0 => 0.,
+            i => self.samples[i - 1].1,
+        }
+    }
+
+    /// Distance travelled along each axis, holding each sample's speed until the next.
+    pub fn output_motion(&self, mode: AccelMode, params: &AllParamArgs) -> (f64, f64) {
+        self.segments().fold((0., 0.), |(x, y), (speed, dt)| {
+            let (x_sens, y_sens) = sensitivity(speed, mode, params);
+            (x + speed * dt * x_sens, y + speed * dt * y_sens)
+        })
+    }
+    pub fn input_motion(&self) -> f64 {
+        self.segments().map(|(speed, dt)| speed * dt).sum()
+    }
+    /// Replays the trace through the curve it was recorded with, falling back
+    /// to `old` if it carries none, and through `new`.
+    pub fn compare(
+        &self,
+        old: (AccelMode, AllParamArgs),
+        new: (AccelMode, AllParamArgs),
+    ) -> ReplayReport {
+        let (mut old_mode, mut old_params) = old;
+        if let Some((mode, values)) = self.curve {
+            old_mode = mode;
+            for &param in ALL_PARAMS {
+                set_param(&mut old_params, param, values[param as usize]);
+            }
+        }
+        ReplayReport {
+            input: self.input_motion(),
+            old: self.output_motion(old_mode, &old_params),
+            new: self.output_motion(new.0, &new.1),
+        }
+    }
+    fn segments(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        // (speed in counts/ms, duration in ms)
+        self.samples.windows(2).map(|w| {
+            let (t0, speed) = w[0];
+            let (t1, _) = w[1];
+            (speed as f64, (t1 - t0).as_secs_f64() * 1000.)
+        })
+    }
+}
+
+impl ReplayReport {
+    /// Relative change in total output distance going from the old curve to the new one.
+    pub fn change(&self) -> f64 {
+        let old = self.old.0.hypot(self.old.1);
+        let new = self.new.0.hypot(self.new.1);
+        if old > 0. { new / old - 1. } else { 0. }
+    }
+}
+
+fn set_param(params: &mut AllParamArgs, param: Param, value: f64) {
+    let field = match param {
+        Param::SensMult => &mut params.sens_mult,
+        Param::YxRatio => &mut params.yx_ratio,
+        Param::InputDpi => &mut params.input_dpi,
+        Param::Accel => &mut params.accel,
+        Param::OffsetLinear => &mut params.offset_linear,
+        Param::OutputCap => &mut params.output_cap,
+        Param::DecayRate => &mut params.decay_rate,
+        Param::OffsetNatural => &mut params.offset_natural,
+        Param::Limit => &mut params.limit,
+        Param::Gamma => &mut params.gamma,
+        Param::Smooth => &mut params.smooth,
+        Param::Motivity => &mut params.motivity,
+        Param::SyncSpeed => &mut params.sync_speed,
+    };
+    *field = value.into();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("maccel-gui-trace-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join(name)
+    }
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+    fn recorded() -> Trace {
+        let mut values = [0.; ALL_PARAMS.len()];
+        values[Param::SensMult as usize] = 1.5;
+        values[Param::Accel as usize] = 0.125;
+        let mut trace = Trace::new(AccelMode::Natural, values);
+        for (t, speed) in [(0, 2.), (10, 4.5), (5, 9.), (20, 0.)] {
+            trace.push(ms(t), speed);
+        }
+        trace
+    }
+
+    #[test]
+    fn saved_traces_load_back_with_their_curve() {
+        let trace = recorded();
+        assert_eq!(trace.samples().len(), 3, "out of order samples are dropped");
+        let path = temp_path("round_trip.txt");
+        trace.save(&path).unwrap();
+
+        let loaded = Trace::load(&path).unwrap();
+        assert_eq!(loaded.curve(), trace.curve());
+        assert_eq!(loaded.samples().len(), trace.samples().len());
+        for (&(t0, s0), &(t1, s1)) in loaded.samples().iter().zip(trace.samples()) {
+            assert!(t0.abs_diff(t1) < Duration::from_micros(1));
+            assert_eq!(s0, s1);
+        }
+        assert_eq!(loaded.speed_at(ms(15)), 4.5);
+        assert_eq!(loaded.speed_at(ms(25)), 0.);
+    }
+
+    #[test]
+    fn comparing_a_curve_with_itself_changes_nothing() {
+        let context = maccel_core::TuiContext::new(crate::RuntimeStore::new(), ALL_PARAMS);
+        let curve = (context.current_mode, context.params_snapshot());
+
+        let trace = recorded();
+        assert_eq!(trace.input_motion(), 2. * 10. + 4.5 * 10.);
+        let mut unrecorded = Trace::default();
+        for &(t, speed) in trace.samples() {
+            unrecorded.push(t, speed);
+        }
+        let report = unrecorded.compare(curve, curve);
+        assert_eq!(report.input, trace.input_motion());
+        assert_eq!(report.old, report.new);
+        assert_eq!(report.change(), 0.);
+
+        // a recorded curve stands in for the old one passed in
+        let values: Vec<_> = ALL_PARAMS
+            .iter()
+            .map(|&p| context.parameter(p).unwrap().value)
+            .collect();
+        let mut with_curve = Trace::new(curve.0, values.try_into().unwrap());
+        for &(t, speed) in trace.samples() {
+            with_curve.push(t, speed);
+        }
+        let mut other = curve;
+        set_param(&mut other.1, Param::SensMult, 3.);
+        let report = with_curve.compare(other, curve);
+        assert_eq!(report.old, report.new);
+
+        let report = ReplayReport {
+            input: 1.,
+            old: (3., 4.),
+            new: (6., 8.),
+        };
+        assert_eq!(report.change(), 1.);
+        assert_eq!(ReplayReport::default().change(), 0.);
+    }
+
+    #[test]
+    fn out_of_range_timestamps_are_malformed() {
+        let path = temp_path("timestamps.txt");
+        for ms in ["1e300", "-1", "inf", "NaN"] {
+            fs::write(&path, format!("0 1.5\n{ms} 2\n")).unwrap();
+            let err = Trace::load(&path).unwrap_err();
+            assert!(format!("{err:#}").contains(":2: malformed sample"), "{ms}");
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use maccel_core::Param;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// How input speeds are shown on the x-axis and in speed-valued params.
+///
+/// The driver evaluates its curve at speeds normalised to 1000 dpi, so a
+/// count there is always a thousandth of an inch, whatever the mouse's DPI.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpeedUnit {
+    #[default]
+    CountsPerMs,
+    InchesPerSec,
+    CmPerSec,
+}
+
+impl SpeedUnit {
+    pub const ALL: [SpeedUnit; 3] = [
+        SpeedUnit::CountsPerMs,
+        SpeedUnit::InchesPerSec,
+        SpeedUnit::CmPerSec,
+    ];
+    /// The DPI the driver normalises input speeds to.
+    pub const NORMAL_DPI: f64 = 1000.;
+
+    /// Params whose values are input speeds in counts/ms.
+    pub fn applies_to(param: Param) -> bool {
+        matches!(
+            param,
+            Param::OffsetLinear | Param::OffsetNatural | Param::SyncSpeed
+        )
+    }
+
+    /// The speed the driver sees for `counts_per_ms` from a mouse at `dpi`.
+    pub fn normalise(counts_per_ms: f64, dpi: f64) -> f64 {
+        if dpi > 0. {
+            counts_per_ms * Self::NORMAL_DPI / dpi
+        } else {
+            counts_per_ms
+        }
+    }
+
+    /// Multiplier from normalised counts/ms to this unit.
+    pub fn factor(self) -> f64 {
+        match self {
+            SpeedUnit::CountsPerMs => 1.,
+            SpeedUnit::InchesPerSec => 1000. / Self::NORMAL_DPI,
+            SpeedUnit::CmPerSec => 2540. / Self::NORMAL_DPI,
+        }
+    }
+    pub fn from_counts_per_ms(self, v: f64) -> f64 {
+        v * self.factor()
+    }
+    pub fn to_counts_per_ms(self, v: f64) -> f64 {
+        v / self.factor()
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            SpeedUnit::CountsPerMs => "counts/ms",
+            SpeedUnit::InchesPerSec => "in/s",
+            SpeedUnit::CmPerSec => "cm/s",
+        }
+    }
+}
+
+impl fmt::Display for SpeedUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.symbol())
+    }
+}
+
+/// A 1, 2 or 5 times power of ten close to `approx`.
+pub fn nice_step(approx: f32) -> f32 {
+    if !approx.is_finite() || approx <= 0. {
+        return 1.;
+    }
+    let magnitude = 10f32.powf(approx.log10().floor());
+    let mantissa = approx / magnitude;
+    magnitude
+        * match mantissa {
+            m if m < 1.5 => 1.,
+            m if m < 3.5 => 2.,
+            m if m < 7.5 => 5.,
+            _ => 10.,
+        }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn speeds_convert_at_the_normalised_dpi() {
+        // 16 counts/ms from a 1600 dpi mouse is 10 normalised counts/ms, i.e. 10 in/s
+        let speed = SpeedUnit::normalise(16., 1600.);
+        assert_eq!(speed, 10.);
+        assert_eq!(SpeedUnit::InchesPerSec.from_counts_per_ms(speed), 10.);
+        assert!((SpeedUnit::CmPerSec.from_counts_per_ms(speed) - 25.4).abs() < 1e-9);
+        assert_eq!(SpeedUnit::CmPerSec.to_counts_per_ms(25.4), 10.);
+        assert_eq!(SpeedUnit::normalise(16., 0.), 16.);
+    }
+
+    #[test]
+    fn steps_are_one_two_or_five() {
+        assert_eq!(nice_step(0.9), 1.);
+        assert_eq!(nice_step(2.6), 2.);
+        assert_eq!(nice_step(40.), 50.);
+        assert_eq!(nice_step(8.), 10.);
+        assert_eq!(nice_step(0.013), 0.01);
+        assert_eq!(nice_step(0.), 1.);
+        assert_eq!(nice_step(f32::NAN), 1.);
+    }
+}